@@ -0,0 +1,3 @@
+fn main() {
+    mixinx_build::scan_crate().unwrap();
+}
@@ -0,0 +1,53 @@
+//源码顺序和宏需要的顺序正好相反: insert写在最前面，declare在后面的模块里，overwrite在insert后面。
+//build.rs扫描过之后，宏按依赖顺序回放，展开的结果和源码顺序无关。
+use mixinx::{expand, insert, overwrite};
+
+pub mod model;
+pub use model::Person;
+
+#[insert(Student)]
+#[derive(Debug, Default)]
+pub struct Employee {
+    pub company: String,
+}
+
+#[insert(Person)]
+#[derive(Debug, Default)]
+pub struct Student {
+    pub school: String,
+}
+
+#[overwrite]
+impl Student {
+    pub fn describe(&self) -> String {
+        format!("{} studies at {}", self.name, self.school)
+    }
+}
+
+#[expand]
+impl Student {
+    pub fn enrolled(&self) -> bool {
+        !self.school.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixin() {
+        let e = Employee {
+            name: "e".into(),
+            age: 30,
+            school: "BJU".into(),
+            company: "xjplke".into(),
+        };
+        assert_eq!(e.describe(), "e studies at BJU");
+        assert_eq!(e.greet(), "hi e");
+        assert!(e.enrolled());
+        assert_eq!(e.get_person().age, 30);
+        assert_eq!(e.get_student().school, "BJU");
+        assert_eq!(e.company, "xjplke");
+    }
+}
@@ -0,0 +1,19 @@
+use mixinx::{declare, expand};
+
+#[expand]
+impl Person {
+    pub fn greet(&self) -> String {
+        format!("hi {}", self.name)
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} is {}", self.name, self.age)
+    }
+}
+
+#[declare]
+#[derive(Debug, Default)]
+pub struct Person {
+    pub name: String,
+    pub age: u32,
+}
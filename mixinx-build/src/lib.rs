@@ -0,0 +1,190 @@
+//! mixinx的build.rs pre-pass。
+//!
+//! 宏是按rustc展开的顺序登记mixin的，`#[insert]` 写在 `#[declare]` 前面、`#[expand]` 在后面的模块里、
+//! `#[overwrite]` 在 `#[insert]` 后面都会出错。在build.rs里调用 [`scan_crate`]，先把整个crate里
+//! 带mixin宏的item收集起来写到一个registry文件里，宏第一次展开的时候按依赖顺序回放一遍，
+//! 展开的结果就和源码顺序无关了。
+//!
+//! ```no_run
+//! // build.rs
+//! mixinx_build::scan_crate().unwrap();
+//! ```
+use quote::ToTokens;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use syn::{Attribute, Item};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("can't parse {0}: {1}")]
+    Parse(PathBuf, syn::Error),
+    #[error("environment variable {0} is not set, scan_crate() must be called from build.rs")]
+    Env(&'static str),
+}
+
+/// 扫描的结果，写到文件里给宏读
+#[derive(Serialize, Debug, Default)]
+pub struct Registry {
+    /// 只有这个crate展开的时候才回放，同一个package的tests/examples是别的crate
+    pub crate_name: String,
+    /// 带mixin宏的item的源码，按源码顺序
+    pub items: Vec<String>,
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
+}
+
+impl Registry {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json()).map_err(|e| Error::Io(path.to_path_buf(), e))
+    }
+}
+
+/// 从crate的根文件(lib.rs/main.rs)开始，顺着 `mod xx;` 找到所有的文件
+pub struct Scanner {
+    root: PathBuf,
+    crate_name: String,
+}
+
+impl Scanner {
+    pub fn new(root: impl Into<PathBuf>, crate_name: impl Into<String>) -> Self {
+        Scanner {
+            root: root.into(),
+            crate_name: crate_name.into().replace('-', "_"),
+        }
+    }
+
+    pub fn scan(&self) -> Result<Registry, Error> {
+        let mut registry = Registry {
+            crate_name: self.crate_name.clone(),
+            ..Registry::default()
+        };
+        self.scan_file(&self.root, true, &mut registry)?;
+        Ok(registry)
+    }
+
+    fn scan_file(&self, path: &Path, is_root: bool, registry: &mut Registry) -> Result<(), Error> {
+        let source = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        let file = syn::parse_file(&source).map_err(|e| Error::Parse(path.to_path_buf(), e))?;
+        registry.files.push(path.to_path_buf());
+        //lib.rs、main.rs、mod.rs里的 mod a; 在同一个目录下，a.rs里的 mod b; 在 a/ 下面
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let child_dir = match path.file_stem().and_then(|s| s.to_str()) {
+            Some("mod") => dir.to_path_buf(),
+            Some(_) if is_root => dir.to_path_buf(),
+            Some(stem) => dir.join(stem),
+            None => dir.to_path_buf(),
+        };
+        self.scan_items(&file.items, dir, &child_dir, registry)
+    }
+
+    fn scan_items(
+        &self,
+        items: &[Item],
+        file_dir: &Path,
+        child_dir: &Path,
+        registry: &mut Registry,
+    ) -> Result<(), Error> {
+        for item in items {
+            //cfg要到编译的时候才知道是不是生效，带cfg的item还是按原来的顺序展开
+            if item_attrs(item).iter().any(|a| a.path().is_ident("cfg")) {
+                continue;
+            }
+            match item {
+                Item::Mod(module) => {
+                    let path_attr = module.attrs.iter().find_map(path_attr);
+                    match &module.content {
+                        Some((_, inner)) => {
+                            let dir = child_dir.join(module.ident.to_string());
+                            self.scan_items(inner, file_dir, &dir, registry)?;
+                        }
+                        None => {
+                            let path = match path_attr {
+                                Some(path) => file_dir.join(path),
+                                None => {
+                                    let name = module.ident.to_string();
+                                    let flat = child_dir.join(format!("{}.rs", name));
+                                    if flat.exists() {
+                                        flat
+                                    } else {
+                                        child_dir.join(name).join("mod.rs")
+                                    }
+                                }
+                            };
+                            self.scan_file(&path, false, registry)?;
+                        }
+                    }
+                }
+                Item::Struct(_) | Item::Enum(_) | Item::Union(_) | Item::Impl(_)
+                    if item_attrs(item).iter().any(is_mixin_macro) =>
+                {
+                    registry.items.push(item.to_token_stream().to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 在build.rs里调用: 扫描当前package的lib(没有lib的话是main)，把registry写到OUT_DIR，
+/// 再通过 `cargo:rustc-env=MIXINX_REGISTRY` 告诉宏文件在哪里
+pub fn scan_crate() -> Result<Registry, Error> {
+    let manifest_dir = PathBuf::from(
+        std::env::var("CARGO_MANIFEST_DIR").map_err(|_| Error::Env("CARGO_MANIFEST_DIR"))?,
+    );
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").map_err(|_| Error::Env("OUT_DIR"))?);
+    let crate_name = std::env::var("CARGO_PKG_NAME").map_err(|_| Error::Env("CARGO_PKG_NAME"))?;
+    let lib = manifest_dir.join("src").join("lib.rs");
+    let root = if lib.exists() {
+        lib
+    } else {
+        manifest_dir.join("src").join("main.rs")
+    };
+    let registry = Scanner::new(root, crate_name).scan()?;
+    let path = out_dir.join("mixinx_registry.json");
+    registry.write(&path)?;
+    for file in registry.files.iter() {
+        println!("cargo:rerun-if-changed={}", file.display());
+    }
+    println!("cargo:rustc-env=MIXINX_REGISTRY={}", path.display());
+    Ok(registry)
+}
+
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Struct(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::Union(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+//和宏用同一个判断，`#[mixinx::insert(..)]` 这种带路径的也算
+fn is_mixin_macro(attr: &Attribute) -> bool {
+    mixinx_core::mixin_macro(attr).is_some()
+}
+
+//#[path = "xx.rs"] mod a;
+fn path_attr(attr: &Attribute) -> Option<String> {
+    if !attr.path().is_ident("path") {
+        return None;
+    }
+    let value = attr.meta.require_name_value().ok()?;
+    match &value.value {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(path),
+            ..
+        }) => Some(path.value()),
+        _ => None,
+    }
+}
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod tests {
+    use mixinx_build::Scanner;
+    use std::path::Path;
+
+    #[test]
+    fn test_scan() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("example/src/lib.rs");
+        let registry = Scanner::new(&root, "mixinx-build-example").scan().unwrap();
+        assert_eq!(registry.crate_name, "mixinx_build_example");
+        //顺着 mod model; 找到了model.rs
+        assert_eq!(registry.files.len(), 2);
+        //带cfg的tests模块不扫描，只有源码里带mixin宏的6个item
+        assert_eq!(registry.items.len(), 6);
+        //按源码顺序: mod model; 写在最前面，它里面的item先出现
+        assert!(registry.items[0].contains("impl Person"));
+        assert!(registry.items[1].contains("struct Person"));
+        assert!(registry.items[2].contains("struct Employee"));
+        assert!(registry
+            .to_json()
+            .starts_with("{\"crate_name\":\"mixinx_build_example\""));
+    }
+}
@@ -0,0 +1,252 @@
+//! `#[insert(..)]` 的参数
+use convert_case::{Case, Casing};
+use proc_macro2::Ident;
+use quote::format_ident;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{Expr, Meta, MetaNameValue};
+
+//insert的参数，例如 #[insert(Person, ctor = default, Student)]
+//选项作用于它前面最近的那个mixin，dump作用于整个insert
+pub(crate) struct InsertArgs {
+    pub(crate) mixins: Vec<InsertMixin>,
+    pub(crate) dump: bool, //展开的结果写到文件里，见dump_item
+}
+
+#[derive(Clone)]
+pub(crate) struct InsertMixin {
+    pub(crate) name: Ident,
+    pub(crate) ctor: CtorMode,
+    pub(crate) rename: Vec<(Ident, Ident)>, //enum variant改名: rename(Register = DevRegister)
+    pub(crate) nest: Option<Ident>,         //nest模式下包装mixin的variant名字
+    pub(crate) embed: Option<Ident>,        //embed模式下mixin作为字段的名字
+    pub(crate) deref: bool,                 //embed时生成Deref/DerefMut
+    pub(crate) as_ref: bool,                //embed时生成AsRef/AsMut
+    pub(crate) serde: SerdeMode,
+    pub(crate) position: FieldPosition,
+    pub(crate) check_layout: bool, //repr(C)时检查mixin的字段是目标类型的前缀
+    pub(crate) prefix: bool,       //前缀布局，生成 as_person/as_person_mut
+}
+
+//展开的字段放在目标类型的什么位置
+#[derive(Clone, PartialEq)]
+pub(crate) enum FieldPosition {
+    Last,
+    First,
+    After(Ident),
+}
+
+//mixin的字段在serde里是什么样子
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SerdeMode {
+    //字段展开到目标类型里(默认)
+    Fields,
+    //embed成一个字段，加上 #[serde(flatten)]，JSON还是平的
+    Flatten,
+    //embed成一个字段，JSON里是嵌套的对象
+    Nested,
+}
+
+//父类型的构造函数(返回Self的关联函数以及Default)怎么继承到目标类型上
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum CtorMode {
+    //目标类型自己的字段作为新增的参数: Employee::new(name, company, workage)
+    Args,
+    //目标类型自己的字段用Default::default()填充，参数和父类型一样
+    Default,
+}
+
+impl Parse for InsertArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let metas = Punctuated::<Meta, Comma>::parse_terminated(input)?;
+        let mut mixins: Vec<InsertMixin> = Vec::new();
+        let mut dump = false;
+        for meta in metas {
+            if meta.path().is_ident("dump") {
+                meta.require_path_only()?;
+                dump = true;
+                continue;
+            }
+            if meta.path().is_ident("ctor") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                let value = meta.require_name_value()?;
+                last.ctor = match &value.value {
+                    Expr::Path(p) if p.path.is_ident("args") => CtorMode::Args,
+                    Expr::Path(p) if p.path.is_ident("default") => CtorMode::Default,
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "expected `ctor = args` or `ctor = default`",
+                        ))
+                    }
+                };
+                continue;
+            }
+            if meta.path().is_ident("rename") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                let list = meta.require_list()?;
+                let pairs =
+                    list.parse_args_with(Punctuated::<MetaNameValue, Comma>::parse_terminated)?;
+                for pair in pairs {
+                    let from = pair.path.require_ident()?.clone();
+                    let to = ident_value(&pair.value, "expected `rename(Variant = NewName)`")?;
+                    last.rename.push((from, to));
+                }
+                continue;
+            }
+            if meta.path().is_ident("nest") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                //nest 或者 nest = Device，默认用mixin的名字作为variant名字
+                last.nest = Some(match &meta {
+                    Meta::Path(_) => last.name.clone(),
+                    Meta::NameValue(value) => {
+                        ident_value(&value.value, "expected `nest` or `nest = Variant`")?
+                    }
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "expected `nest` or `nest = Variant`",
+                        ))
+                    }
+                });
+                continue;
+            }
+            if meta.path().is_ident("embed") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                //embed 或者 embed = person，默认用mixin名字的snake case作为字段名
+                last.embed = Some(match &meta {
+                    Meta::Path(_) => {
+                        format_ident!("{}", last.name.to_string().to_case(Case::Snake))
+                    }
+                    Meta::NameValue(value) => {
+                        ident_value(&value.value, "expected `embed` or `embed = field`")?
+                    }
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "expected `embed` or `embed = field`",
+                        ))
+                    }
+                });
+                continue;
+            }
+            if meta.path().is_ident("serde") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                let value = meta.require_name_value()?;
+                last.serde = match &value.value {
+                    Expr::Path(p) if p.path.is_ident("fields") => SerdeMode::Fields,
+                    Expr::Path(p) if p.path.is_ident("flatten") => SerdeMode::Flatten,
+                    Expr::Path(p) if p.path.is_ident("nested") => SerdeMode::Nested,
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "expected `serde = fields`, `serde = flatten` or `serde = nested`",
+                        ))
+                    }
+                };
+                //flatten和nested都需要把mixin作为一个字段
+                if last.serde != SerdeMode::Fields && last.embed.is_none() {
+                    last.embed = Some(format_ident!(
+                        "{}",
+                        last.name.to_string().to_case(Case::Snake)
+                    ));
+                }
+                continue;
+            }
+            if meta.path().is_ident("position") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                let value = meta.require_name_value()?;
+                last.position = match &value.value {
+                    Expr::Path(p) if p.path.is_ident("first") => FieldPosition::First,
+                    Expr::Path(p) if p.path.is_ident("last") => FieldPosition::Last,
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "expected `position = first` or `position = last`",
+                        ))
+                    }
+                };
+                continue;
+            }
+            if meta.path().is_ident("after") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                let value = meta.require_name_value()?;
+                last.position =
+                    FieldPosition::After(ident_value(&value.value, "expected `after = field`")?);
+                continue;
+            }
+            if meta.path().is_ident("check_layout") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                meta.require_path_only()?;
+                last.check_layout = true;
+                continue;
+            }
+            //prefix = position = first + check_layout，再加上转换成mixin引用的函数
+            if meta.path().is_ident("prefix") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                meta.require_path_only()?;
+                last.position = FieldPosition::First;
+                last.check_layout = true;
+                last.prefix = true;
+                continue;
+            }
+            if meta.path().is_ident("deref") || meta.path().is_ident("as_ref") {
+                let last = last_mixin(&mut mixins, &meta)?;
+                meta.require_path_only()?;
+                if meta.path().is_ident("deref") {
+                    last.deref = true;
+                } else {
+                    last.as_ref = true;
+                }
+                continue;
+            }
+            let Meta::Path(path) = &meta else {
+                return Err(syn::Error::new_spanned(meta, "unknown insert option"));
+            };
+            let Some(name) = path.get_ident() else {
+                return Err(syn::Error::new_spanned(path, "expected a mixin name"));
+            };
+            //同一个mixin写两次的话，后面的选项会被当成是前一个的
+            if mixins.iter().any(|m| m.name == *name) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!("mixin `{}` is inserted twice", name),
+                ));
+            }
+            mixins.push(InsertMixin {
+                name: name.clone(),
+                ctor: CtorMode::Args,
+                rename: Vec::new(),
+                nest: None,
+                embed: None,
+                deref: false,
+                as_ref: false,
+                serde: SerdeMode::Fields,
+                position: FieldPosition::Last,
+                check_layout: false,
+                prefix: false,
+            });
+        }
+        Ok(InsertArgs { mixins, dump })
+    }
+}
+
+//选项的值只能是一个名字: nest = Device, after = id
+fn ident_value(expr: &Expr, expected: &str) -> syn::Result<Ident> {
+    match expr {
+        Expr::Path(p) => p
+            .path
+            .get_ident()
+            .cloned()
+            .ok_or_else(|| syn::Error::new_spanned(p, expected)),
+        other => Err(syn::Error::new_spanned(other, expected)),
+    }
+}
+
+//选项作用于前面最近的mixin
+fn last_mixin<'a>(mixins: &'a mut [InsertMixin], meta: &Meta) -> syn::Result<&'a mut InsertMixin> {
+    mixins
+        .last_mut()
+        .ok_or_else(|| syn::Error::new_spanned(meta, "option must follow the mixin it applies to"))
+}
@@ -0,0 +1,223 @@
+//! 父类型的构造函数和Default，字段合并完之后链到目标类型上
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{
+    parse_quote, Attribute, Data, DataStruct, DeriveInput, Field, Fields, FnArg, GenericArgument,
+    ImplItemFn, ItemImpl, Pat, PatIdent, PathArguments, ReturnType, Type, TypePath,
+};
+
+use crate::args::{CtorMode, InsertMixin};
+use crate::rewrite::retarget_impl;
+use crate::util::{has_mixin_flag, is_cfg};
+use crate::Error;
+
+//等字段合并完之后再生成的构造函数
+pub(crate) struct MixedCtor {
+    pub(crate) parent: InsertMixin,
+    pub(crate) parent_fields: Punctuated<Field, Comma>,
+    pub(crate) ctor: CtorKind,
+    pub(crate) key: String,    //fn或者trait的key
+    pub(crate) origin: String, //最早定义这个构造函数的类型，菱形继承时用来决定留哪一份
+}
+
+pub(crate) enum CtorKind {
+    Fn(Box<ItemImpl>), //只包含这个构造函数的impl块
+    Default(String),   //trait的key
+}
+
+//构造函数返回的是Self，还是包在Option/Result里的Self
+#[derive(Clone, Copy, PartialEq)]
+enum CtorReturn {
+    Plain,
+    //Option<Self>、Result<Self, E>，用map把父类型的值换成目标类型
+    Wrapped,
+}
+
+//没有self参数并且返回Self(或者Option<Self>、Result<Self, E>)的关联函数当作构造函数。
+//-> Person 在这之前已经被换成了 -> Self；其他包着Self的返回类型(Vec<Self>等)当普通的fn继承
+fn ctor_return(impl_fn: &ImplItemFn) -> Option<CtorReturn> {
+    if impl_fn.sig.receiver().is_some() || has_mixin_flag(&impl_fn.attrs, "keep_type") {
+        return None;
+    }
+    let ReturnType::Type(_, ty) = &impl_fn.sig.output else {
+        return None;
+    };
+    let is_self = |ty: &Type| matches!(ty, Type::Path(TypePath { qself: None, path }) if path.is_ident("Self"));
+    if is_self(ty) {
+        return Some(CtorReturn::Plain);
+    }
+    let Type::Path(TypePath { qself: None, path }) = ty.as_ref() else {
+        return None;
+    };
+    let last = path.segments.last()?;
+    if last.ident != "Option" && last.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(first)) if is_self(first) => Some(CtorReturn::Wrapped),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_ctor(impl_fn: &ImplItemFn) -> bool {
+    ctor_return(impl_fn).is_some()
+}
+
+pub(crate) fn is_default_impl(trait_impl: &ItemImpl) -> bool {
+    match &trait_impl.trait_ {
+        Some((_, path, _)) => path
+            .segments
+            .last()
+            .is_some_and(|seg| seg.ident == "Default" && seg.arguments.is_none()),
+        None => false,
+    }
+}
+
+//目标类型的字段里面不属于父类型的部分
+fn own_fields<'a>(
+    parent_fields: &Punctuated<Field, Comma>,
+    target: &'a DeriveInput,
+) -> Vec<&'a Field> {
+    let parent_names: HashSet<String> = parent_fields
+        .iter()
+        .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+        .collect();
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(named),
+        ..
+    }) = &target.data
+    else {
+        return Vec::new();
+    };
+    named
+        .named
+        .iter()
+        .filter(|f| {
+            f.ident
+                .as_ref()
+                .is_some_and(|i| !parent_names.contains(&i.to_string()))
+        })
+        .collect()
+}
+
+//先调用父类型的构造函数，再把父类型的字段挪过来，目标类型自己的字段用参数或者默认值填充
+//fn new(n: String, company: String, workage: i32) -> Self {
+//    let __parent = Person::new(n);
+//    Self { name: __parent.name, age: __parent.age, company, workage }
+//}
+//返回Option<Self>/Result<Self, E>的: Person::parse(s).map(|__parent| Self { .. })
+pub(crate) fn gen_chained_ctor(
+    mixed: &MixedCtor,
+    ctor: &ImplItemFn,
+    target: &DeriveInput,
+) -> ImplItemFn {
+    let parent = &mixed.parent.name;
+    let own = own_fields(&mixed.parent_fields, target);
+    let own_names: HashSet<String> = own
+        .iter()
+        .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+        .collect();
+
+    let mut chained = ctor.clone();
+    let mut forward_args = Vec::new();
+    for (i, input) in chained.sig.inputs.iter_mut().enumerate() {
+        let FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        //参数是复杂的pattern或者和自己的字段重名时，改成 __argN 再转发给父类型
+        let arg = match pat_type.pat.as_ref() {
+            Pat::Ident(PatIdent {
+                ident,
+                by_ref: None,
+                subpat: None,
+                ..
+            }) if !own_names.contains(&ident.to_string()) => ident.clone(),
+            _ => format_ident!("__arg{}", i),
+        };
+        *pat_type.pat = parse_quote!(#arg);
+        forward_args.push(arg);
+    }
+
+    let parent_inits = parent_inits(mixed);
+    let mut own_inits = Vec::new();
+    for f in own.iter() {
+        let name = f.ident.clone().unwrap();
+        let ty = &f.ty;
+        let attrs: Vec<&Attribute> = f.attrs.iter().filter(|a| is_cfg(a)).collect();
+        if mixed.parent.ctor == CtorMode::Args {
+            chained.sig.inputs.push(parse_quote!(#(#attrs)* #name: #ty));
+            own_inits.push(quote! { #(#attrs)* #name });
+        } else {
+            own_inits.push(quote! { #(#attrs)* #name: ::core::default::Default::default() });
+        }
+    }
+
+    let fn_name = &ctor.sig.ident;
+    chained.block = match ctor_return(ctor) {
+        Some(CtorReturn::Wrapped) => parse_quote!({
+            #parent::#fn_name(#(#forward_args),*).map(|__parent| Self {
+                #(#parent_inits,)*
+                #(#own_inits,)*
+            })
+        }),
+        _ => parse_quote!({
+            let __parent = #parent::#fn_name(#(#forward_args),*);
+            Self {
+                #(#parent_inits,)*
+                #(#own_inits,)*
+            }
+        }),
+    };
+    chained
+}
+
+//构造函数里父类型的部分: 展开的字段从__parent里取，embed的话整个__parent就是那个字段
+fn parent_inits(mixed: &MixedCtor) -> Vec<TokenStream> {
+    if let Some(field) = mixed.parent.embed.as_ref() {
+        return vec![quote! { #field: __parent }];
+    }
+    mixed
+        .parent_fields
+        .iter()
+        .filter_map(|f| {
+            let name = f.ident.as_ref()?;
+            let attrs = f.attrs.iter().filter(|a| is_cfg(a));
+            Some(quote! { #(#attrs)* #name: __parent.#name })
+        })
+        .collect()
+}
+
+//父类型上expand的 impl Default，目标类型多出来的字段用Default::default()填充
+pub(crate) fn gen_chained_default(
+    mixed: &MixedCtor,
+    target: &DeriveInput,
+) -> Result<ItemImpl, Error> {
+    let parent = &mixed.parent.name;
+    let parent_inits = parent_inits(mixed);
+    let own_inits = own_fields(&mixed.parent_fields, target)
+        .into_iter()
+        .map(|f| {
+            let name = f.ident.as_ref().unwrap();
+            let attrs = f.attrs.iter().filter(|a| is_cfg(a));
+            quote! { #(#attrs)* #name: ::core::default::Default::default() }
+        });
+    let mut default_impl: ItemImpl = parse_quote! {
+        impl ::core::default::Default for #parent {
+            fn default() -> Self {
+                let __parent: #parent = ::core::default::Default::default();
+                Self {
+                    #(#parent_inits,)*
+                    #(#own_inits,)*
+                }
+            }
+        }
+    };
+    retarget_impl(&mut default_impl, target)?;
+    Ok(default_impl)
+}
@@ -0,0 +1,327 @@
+//! `#[declare]`、`#[expand]`、`#[overwrite]`，以及struct的get_*/set_*方法
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use std::collections::HashMap;
+use syn::visit_mut::VisitMut;
+use syn::{
+    parse_quote, Attribute, Data, DataStruct, DeriveInput, Fields, FieldsNamed, ForeignItemFn,
+    ImplItem, ItemImpl,
+};
+
+use crate::dump::dump_item;
+use crate::mixin::{impl_fn_keys, load_mixin, DeclareOptions, Mixin, MixinCtx};
+use crate::origin::Ancestry;
+use crate::registry::Registry;
+use crate::replay::Replayed;
+use crate::rewrite::MixinAttrStripper;
+use crate::suggest::no_mixin;
+use crate::util::{get_name_of_impl, is_cfg, key_base};
+use crate::{Error, MixinType};
+
+pub(crate) fn mixin_type_of(input: &DeriveInput) -> Result<MixinType, syn::Error> {
+    match &input.data {
+        Data::Struct(_) => Ok(MixinType::Struct),
+        Data::Enum(_) => Ok(MixinType::Enum),
+        Data::Union(data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            "unions can't be mixins, use a struct or an enum",
+        )),
+    }
+}
+
+/// `get_xxx`/`set_xxx`，xxx是类型名的snake case。只有带名字字段的struct才有，tuple struct和unit struct返回None
+pub fn gen_get_set_impls(input: &DeriveInput) -> Option<ItemImpl> {
+    let name = &input.ident;
+
+    let name_string = name.to_string();
+    let get_fn_name = Ident::new(
+        &("get".to_owned() + &name_string).to_case(Case::Snake),
+        name.span(),
+    );
+    let set_fn_name = Ident::new(
+        &("set".to_owned() + &name_string).to_case(Case::Snake),
+        name.span(),
+    );
+
+    let fields = if let Data::Struct(DataStruct {
+        fields: Fields::Named(FieldsNamed { named, .. }),
+        ..
+    }) = &input.data
+    {
+        named
+    } else {
+        return None;
+    };
+    //字段上的#[cfg]也要加到对应的get/set语句上
+    let fds: Vec<(&Ident, Vec<&Attribute>)> = fields
+        .into_iter()
+        .filter_map(|f| {
+            let cfgs = f.attrs.iter().filter(|a| is_cfg(a)).collect();
+            Some((f.ident.as_ref()?, cfgs))
+        })
+        .collect();
+    let get_fds_token: Vec<TokenStream> = fds
+        .iter()
+        .map(|(name, cfgs)| quote! { #(#cfgs)* #name: self.#name.clone() })
+        .collect();
+    let set_fds_token: Vec<TokenStream> = fds
+        .iter()
+        .map(|(name, cfgs)| {
+            if cfgs.is_empty() {
+                quote! { self.#name = p.#name.clone() }
+            } else {
+                //赋值表达式上不能直接加属性，放到block里
+                quote! { #(#cfgs)* { self.#name = p.#name.clone(); } }
+            }
+        })
+        .collect();
+    //generate get/set functions
+    //https://docs.rs/syn/latest/syn/struct.Generics.html
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let impl_get_set = parse_quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[mixin(keep_type)]
+            pub fn #get_fn_name(&self) -> #name #ty_generics{
+                #name {
+                    #(#get_fds_token,)*
+                }
+            }
+            #[mixin(keep_type)]
+            pub fn #set_fn_name(&mut self, p: &#name #ty_generics){
+                #(#set_fds_token;)*
+            }
+        }
+    };
+    //dbg!(impl_get_set.to_token_stream().to_string());
+    Some(impl_get_set)
+}
+
+pub(crate) fn declare_impl(
+    registry: &mut Registry,
+    attribute: TokenStream,
+    input: TokenStream,
+    replayed: Option<&Replayed>,
+) -> Result<TokenStream, Error> {
+    let options = DeclareOptions::parse(attribute)?;
+    // Keep it just to let the compiler check it
+    let mut output: TokenStream = "#[allow(dead_code)]".parse()?;
+    output.extend(input.clone());
+
+    let input = syn::parse2::<DeriveInput>(input)?;
+    let mixin_type = mixin_type_of(&input)?;
+
+    let name_string = input.ident.clone().to_string();
+    if replayed.is_none() {
+        registry.check_undeclared("declare", &input.ident)?;
+    }
+
+    let mut get_set_impls = None;
+
+    //abstract的类型不会输出，get/set也没有意义
+    if mixin_type == MixinType::Struct && !options.is_abstract {
+        get_set_impls = gen_get_set_impls(&input);
+    }
+
+    let mut mixin_ctx = MixinCtx {
+        name: input.ident.clone(),
+        mixin_type: mixin_type.clone(),
+        declaration: Some(input),
+        extensions: Vec::new(),
+        overwrite_impls: Vec::new(),
+        impl_traits: HashMap::new(),
+        over_traits: HashMap::new(),
+        options,
+        required: Vec::new(),
+        ancestry: Ancestry::default(),
+    };
+
+    if let Some(get_set_impls) = get_set_impls {
+        for key in impl_fn_keys(&get_set_impls) {
+            mixin_ctx
+                .ancestry
+                .fn_origins
+                .insert(key, name_string.clone());
+        }
+        mixin_ctx.add_extension(&get_set_impls);
+    }
+    if let Some(Data::Struct(DataStruct { fields, .. })) =
+        mixin_ctx.declaration.as_ref().map(|d| &d.data)
+    {
+        for ident in fields.iter().filter_map(|f| f.ident.as_ref()) {
+            mixin_ctx
+                .ancestry
+                .field_origins
+                .insert(ident.to_string(), name_string.clone());
+        }
+    }
+
+    let mixin = (&mixin_ctx).into();
+    //回放过的话registry里已经有了，而且还带着它的expand
+    if replayed.is_none() {
+        registry.mixins.insert(name_string, mixin);
+    }
+    if mixin_ctx.options.is_abstract {
+        return Ok(TokenStream::new());
+    }
+    let output = mixin_ctx.to_token_stream();
+    dump_item(&mixin_ctx, &output, false);
+    Ok(output)
+}
+
+pub(crate) fn expand_impl(
+    registry: &mut Registry,
+    input: TokenStream,
+    replayed: Option<&Replayed>,
+) -> Result<TokenStream, Error> {
+    let mut input = syn::parse2::<ItemImpl>(input)?;
+    //#[required] fn id(&self) -> u64; 没有方法体，syn解析成Verbatim，单独记录下来，不输出
+    let mut required = Vec::new();
+    for item in std::mem::take(&mut input.items) {
+        if let ImplItem::Verbatim(tokens) = &item {
+            if let Ok(mut required_fn) = syn::parse2::<ForeignItemFn>(tokens.clone()) {
+                if required_fn
+                    .attrs
+                    .iter()
+                    .any(|a| a.path().is_ident("required"))
+                {
+                    if input.trait_.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            &required_fn.sig,
+                            "#[required] only applies to inherent impls",
+                        )
+                        .into());
+                    }
+                    required_fn.attrs.retain(|a| !a.path().is_ident("required"));
+                    required.push(required_fn);
+                    continue;
+                }
+            }
+        }
+        input.items.push(item);
+    }
+    let mut output_impl = input.clone();
+    MixinAttrStripper.visit_item_impl_mut(&mut output_impl);
+    let output = output_impl.to_token_stream();
+
+    let (name, trait_name) = get_name_of_impl(&input)?;
+
+    let data = &mut registry.mixins;
+    if replayed.is_some() {
+        return Ok(if data.get(&name).is_some_and(|m| m.options.is_abstract) {
+            TokenStream::new()
+        } else {
+            output
+        });
+    }
+    //类型本身declare失败的时候已经报过错了，类型照样输出了，impl也照样输出，不再重复报错
+    if !data.contains_key(&name) && registry.failed.contains(&name) {
+        return Ok(output);
+    }
+    let mixin = data
+        .get(&name)
+        .ok_or_else(|| no_mixin("expand", &input.self_ty, &name, data, &registry.failed))?; //extend不能放在结构体declear的前面。
+
+    let mut mixin_ctx = load_mixin(mixin, &input.self_ty)?;
+    //abstract的类型没有输出，它的impl也只是给insert用的
+    let output = if mixin_ctx.options.is_abstract {
+        TokenStream::new()
+    } else {
+        output
+    };
+
+    for required_fn in required {
+        mixin_ctx
+            .required
+            .retain(|r| r.sig.ident != required_fn.sig.ident);
+        mixin_ctx.required.push(required_fn);
+    }
+    //expand进来的fn/trait都算这个类型自己定义的
+    if !trait_name.is_empty() {
+        mixin_ctx
+            .ancestry
+            .trait_origins
+            .insert(trait_name.clone(), name.clone());
+        mixin_ctx.impl_traits.insert(trait_name, input);
+    } else {
+        for key in impl_fn_keys(&input) {
+            mixin_ctx.ancestry.fn_origins.insert(key, name.clone());
+        }
+        mixin_ctx.add_extension(&input);
+    }
+
+    let mixin: Mixin = (&mixin_ctx).into();
+    data.insert(name, mixin);
+
+    Ok(output)
+}
+
+pub(crate) fn overwrite_impl(
+    registry: &mut Registry,
+    input: TokenStream,
+    replayed: Option<&Replayed>,
+) -> Result<TokenStream, Error> {
+    let input = syn::parse2::<ItemImpl>(input)?;
+    if replayed.is_some() {
+        return Ok(TokenStream::new());
+    }
+
+    let (name, trait_name) = get_name_of_impl(&input)?;
+
+    let data = &mut registry.mixins;
+
+    let mut mixin_ctx = if let Some(mixin) = data.get(&name) {
+        load_mixin(mixin, &input.self_ty)?
+    } else {
+        //没有找到就新建一个并且放到全局变量,这个时候还不知道当前结构是 Struct还是enum。
+        MixinCtx {
+            name: Ident::new(&name, Span::call_site()),
+            mixin_type: MixinType::Unknown,
+            declaration: None,
+            extensions: Vec::new(),
+            overwrite_impls: Vec::new(),
+            impl_traits: HashMap::new(),
+            over_traits: HashMap::new(),
+            options: DeclareOptions::default(),
+            required: Vec::new(),
+            ancestry: Ancestry::default(),
+        }
+    };
+
+    if mixin_ctx.declaration.is_some() {
+        //如果这里先找到了mixin，并且declaration有值， 说明当前的这个overwrite没放到insert之前
+        return Err(syn::Error::new_spanned(
+            &input.self_ty,
+            format!(
+                "`{}` is already declared; place #[overwrite] before #[insert(..)] on `{}`",
+                name, name
+            ),
+        )
+        .into());
+    }
+
+    if trait_name.is_empty() {
+        //当前是不带trait的overwrie，需要把函数拆解出来
+        mixin_ctx.add_overwrite_impls(&input);
+    } else {
+        if mixin_ctx.over_traits.contains_key(&trait_name) {
+            let trait_path = input.trait_.as_ref().map(|(_, path, _)| path);
+            return Err(syn::Error::new_spanned(
+                trait_path,
+                format!(
+                    "trait `{}` is already overwritten for `{}`",
+                    key_base(&trait_name),
+                    name
+                ),
+            )
+            .into());
+        }
+        mixin_ctx.over_traits.insert(trait_name, input);
+    }
+
+    let mixin = Mixin::from(&mixin_ctx);
+    data.insert(name, mixin);
+
+    let output = TokenStream::new();
+    Ok(output) //这里需要返回空的TokenStream，实际在的调用insert的时候再输出。
+}
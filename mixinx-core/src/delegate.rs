@@ -0,0 +1,260 @@
+//! embed和nest的转发: 方法和trait转发给包起来的字段或者variant
+use proc_macro2::{Ident, TokenStream, TokenTree as TokenTree2};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse_quote, DeriveInput, FnArg, ImplItem, ImplItemFn, ItemImpl, Pat, PatIdent, ReturnType,
+    Type, TypePath,
+};
+
+use crate::args::InsertMixin;
+use crate::mixin::{impl_fn_keys, MixinCtx};
+use crate::rewrite::retarget_impl;
+use crate::util::key_base;
+use crate::Error;
+
+pub(crate) fn overwrites_trait(ctx: &MixinCtx, trait_name: &str) -> bool {
+    ctx.over_traits
+        .keys()
+        .any(|k| key_base(k) == key_base(trait_name))
+}
+
+pub(crate) fn overwrites_fn(ctx: &MixinCtx, fn_name: &Ident) -> bool {
+    ctx.overwrite_impls
+        .iter()
+        .flat_map(impl_fn_keys)
+        .any(|k| *fn_name == key_base(&k))
+}
+
+//方法没办法转发给包起来的值(参数里有Self之类的)，只能让目标类型overwrite。
+//trait_name是None的时候是mixin自己的方法，否则整个trait都继承不了
+pub(crate) fn unforwardable(
+    insert_mixin: &InsertMixin,
+    trait_name: Option<&str>,
+    fn_name: &Ident,
+    target: &str,
+) -> syn::Error {
+    let mixin = &insert_mixin.name;
+    let wrapped = match (&insert_mixin.nest, &insert_mixin.embed) {
+        (Some(nest), _) => format!("`{}::{}`", target, nest),
+        (_, Some(field)) => format!("`{}.{}`", target, field),
+        _ => format!("`{}`", target),
+    };
+    let (what, how) = match trait_name {
+        Some(trait_name) => (
+            format!("`{}::{}` of `{}`", key_base(trait_name), fn_name, mixin),
+            format!(
+                "implement the trait yourself with `#[overwrite] impl {} for {} {{ .. }}`",
+                key_base(trait_name),
+                target
+            ),
+        ),
+        None => (
+            format!("`{}::{}`", mixin, fn_name),
+            format!(
+                "implement `{}` yourself in `#[overwrite] impl {} {{ .. }}`",
+                fn_name, target
+            ),
+        ),
+    };
+    //embed的字段没办法从返回值里的Self取出来，nest可以包一层variant
+    let allowed = match insert_mixin.embed {
+        Some(_) => "the receiver",
+        None => "the receiver or the return type",
+    };
+    syn::Error::new_spanned(
+        mixin,
+        format!(
+            "{} can't be forwarded to the `{}` in {}: it uses `Self` other than as {}; \
+             {} placed before the insert",
+            what, mixin, wrapped, allowed, how
+        ),
+    )
+}
+
+//trait impl里的方法都转发给包起来的值，有一个转发不了整个trait就报错
+pub(crate) fn forward_trait_impl(
+    trait_impl: &ItemImpl,
+    trait_name: &str,
+    insert_mixin: &InsertMixin,
+    via: &DelegateVia,
+    target: &DeriveInput,
+) -> Result<ItemImpl, Error> {
+    let mixin_ident = &insert_mixin.name;
+    let mut trait_impl = trait_impl.clone();
+    let trait_path = trait_impl.trait_.as_ref().unwrap().1.clone();
+    let mut items = Vec::new();
+    for item in std::mem::take(&mut trait_impl.items) {
+        let ImplItem::Fn(impl_fn) = item else {
+            items.push(item);
+            continue;
+        };
+        let fn_name = &impl_fn.sig.ident;
+        let callee = quote! { <#mixin_ident as #trait_path>::#fn_name };
+        let delegate = gen_delegate(&impl_fn, callee, via).ok_or_else(|| {
+            unforwardable(
+                insert_mixin,
+                Some(trait_name),
+                fn_name,
+                &target.ident.to_string(),
+            )
+        })?;
+        items.push(ImplItem::Fn(delegate));
+    }
+    trait_impl.items = items;
+    retarget_impl(&mut trait_impl, target)?;
+    Ok(trait_impl)
+}
+
+//方法转发到哪里: nest是enum包起来的variant，embed是struct里的字段
+pub(crate) enum DelegateVia<'a> {
+    Variant(&'a Ident),
+    Field(&'a Ident),
+}
+
+//mixin的方法转发给包起来的值:
+//nest:  fn code(&self, a: u8) -> u8 { match self { Self::Device(__inner) => DeviceMessage::code(__inner, a), } }
+//embed: fn age(&self) -> i32 { Person::age(&self.person) }
+//nest时返回Self的包一层 Self::Device(..)；参数或返回值里其他地方用到Self的没办法转发，返回None
+pub(crate) fn gen_delegate(
+    impl_fn: &ImplItemFn,
+    callee: TokenStream,
+    via: &DelegateVia,
+) -> Option<ImplItemFn> {
+    let mut delegate = impl_fn.clone();
+    let mut args = Vec::new();
+    let mut receiver_expr = None;
+    for (i, input) in delegate.sig.inputs.iter_mut().enumerate() {
+        match input {
+            FnArg::Receiver(receiver) => {
+                //self: Box<Self> 之类的不支持
+                if receiver.colon_token.is_some() {
+                    return None;
+                }
+                receiver_expr = Some(match via {
+                    DelegateVia::Variant(_) => quote! { __inner },
+                    DelegateVia::Field(field) => {
+                        match (&receiver.reference, &receiver.mutability) {
+                            (Some(_), Some(_)) => quote! { &mut self.#field },
+                            (Some(_), None) => quote! { &self.#field },
+                            (None, _) => quote! { self.#field },
+                        }
+                    }
+                });
+            }
+            FnArg::Typed(pat_type) => {
+                if mentions_self(pat_type.ty.to_token_stream()) {
+                    return None;
+                }
+                let arg = match pat_type.pat.as_ref() {
+                    Pat::Ident(PatIdent {
+                        ident,
+                        by_ref: None,
+                        subpat: None,
+                        ..
+                    }) if ident != "__inner" => ident.clone(),
+                    _ => format_ident!("__arg{}", i),
+                };
+                *pat_type.pat = parse_quote!(#arg);
+                args.push(arg);
+            }
+        }
+    }
+    let returns_self = match &delegate.sig.output {
+        ReturnType::Type(_, ty) if matches!(ty.as_ref(), Type::Path(TypePath { qself: None, path }) if path.is_ident("Self")) =>
+        {
+            //embed的构造函数单独处理
+            if matches!(via, DelegateVia::Field(_)) {
+                return None;
+            }
+            true
+        }
+        ReturnType::Type(_, ty) if mentions_self(ty.to_token_stream()) => return None,
+        _ => false,
+    };
+    if mentions_self(delegate.sig.generics.to_token_stream()) {
+        return None;
+    }
+
+    let mut call = match receiver_expr.as_ref() {
+        Some(receiver) => quote! { #callee(#receiver, #(#args),*) },
+        None => quote! { #callee(#(#args),*) },
+    };
+    if delegate.sig.asyncness.is_some() {
+        call = quote! { #call.await };
+    }
+    if delegate.sig.unsafety.is_some() {
+        call = quote! { unsafe { #call } };
+    }
+    delegate.block = match via {
+        DelegateVia::Variant(nest) => {
+            if returns_self {
+                call = quote! { Self::#nest(#call) };
+            }
+            if receiver_expr.is_some() {
+                parse_quote!({
+                    match self {
+                        Self::#nest(__inner) => #call,
+                    }
+                })
+            } else {
+                parse_quote!({ #call })
+            }
+        }
+        DelegateVia::Field(_) => parse_quote!({ #call }),
+    };
+    Some(delegate)
+}
+
+fn mentions_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree2::Ident(idt) => idt == "Self",
+        TokenTree2::Group(g) => mentions_self(g.stream()),
+        _ => false,
+    })
+}
+
+//embed时可选的 Deref/DerefMut、AsRef/AsMut
+pub(crate) fn gen_embed_impls(
+    target: &DeriveInput,
+    insert_mixin: &InsertMixin,
+    field: &Ident,
+) -> Vec<ItemImpl> {
+    let name = &target.ident;
+    let mixin = &insert_mixin.name;
+    let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+    let mut impls = Vec::new();
+    if insert_mixin.deref {
+        impls.push(parse_quote! {
+            impl #impl_generics ::core::ops::Deref for #name #ty_generics #where_clause {
+                type Target = #mixin;
+                fn deref(&self) -> &#mixin {
+                    &self.#field
+                }
+            }
+        });
+        impls.push(parse_quote! {
+            impl #impl_generics ::core::ops::DerefMut for #name #ty_generics #where_clause {
+                fn deref_mut(&mut self) -> &mut #mixin {
+                    &mut self.#field
+                }
+            }
+        });
+    }
+    if insert_mixin.as_ref {
+        impls.push(parse_quote! {
+            impl #impl_generics ::core::convert::AsRef<#mixin> for #name #ty_generics #where_clause {
+                fn as_ref(&self) -> &#mixin {
+                    &self.#field
+                }
+            }
+        });
+        impls.push(parse_quote! {
+            impl #impl_generics ::core::convert::AsMut<#mixin> for #name #ty_generics #where_clause {
+                fn as_mut(&mut self) -> &mut #mixin {
+                    &mut self.#field
+                }
+            }
+        });
+    }
+    impls
+}
@@ -0,0 +1,81 @@
+//! MIXINX_DUMP或者 `#[insert(.., dump)]`: 把宏展开之后的源码写到文件里
+use proc_macro2::TokenStream;
+use std::path::PathBuf;
+
+use crate::graph::GraphNode;
+use crate::mixin::MixinCtx;
+
+//MIXINX_DUMP=target/mixinx 或者 #[insert(.., dump)]: 宏最终输出的类型和impl格式化之后写到 <目录>/<crate>/<类型>.rs，
+//开头注明每个字段、方法、trait是从哪个mixin来的，继承来的代码编译出错的时候可以对着看。
+//只是调试用的，写不了文件也不影响展开
+pub(crate) fn dump_item(mixin: &MixinCtx, output: &TokenStream, requested: bool) {
+    let dir = match std::env::var_os("MIXINX_DUMP") {
+        Some(dir) => PathBuf::from(dir),
+        None if requested => target_dir().join("mixinx"),
+        None => return,
+    };
+    let dir = match std::env::var("CARGO_CRATE_NAME") {
+        Ok(crate_name) => dir.join(crate_name),
+        Err(_) => dir,
+    };
+    let Ok(file) = syn::parse2::<syn::File>(output.clone()) else {
+        return;
+    };
+    let text = format!("{}\n{}", provenance(mixin), prettyplease::unparse(&file));
+    let path = dir.join(format!("{}.rs", mixin.name));
+    //内容没变就不写，免得每次编译都改文件
+    if std::fs::read_to_string(&path).is_ok_and(|old| old == text) {
+        return;
+    }
+    let _ = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, text));
+}
+
+//cargo不把target目录告诉rustc: 设置了CARGO_TARGET_DIR就用它，否则是workspace根目录下的target
+fn target_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return PathBuf::from(dir);
+    }
+    let Some(manifest_dir) = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from) else {
+        return PathBuf::from("target");
+    };
+    let root = manifest_dir
+        .ancestors()
+        .filter(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml"))
+                .is_ok_and(|manifest| manifest.contains("[workspace]"))
+        })
+        .last()
+        .unwrap_or(&manifest_dir)
+        .to_path_buf();
+    root.join("target")
+}
+
+//dump文件开头的注释: 祖先，以及字段、方法、trait各自的来源
+fn provenance(mixin: &MixinCtx) -> String {
+    let node = GraphNode::new(mixin);
+    let mut lines = vec![format!("// `{}`, expanded by mixinx", node.name)];
+    if !node.ancestors.is_empty() {
+        lines.push(format!("// ancestors: {}", node.ancestors.join(" -> ")));
+    }
+    for (title, rows) in [
+        ("fields", &node.fields),
+        ("methods", &node.methods),
+        ("traits", &node.traits),
+    ] {
+        if rows.is_empty() {
+            continue;
+        }
+        lines.push("//".into());
+        lines.push(format!("// {}:", title));
+        let width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0);
+        for row in rows {
+            lines.push(format!(
+                "//     {:width$}  from {}",
+                row.name,
+                row.from,
+                width = width
+            ));
+        }
+    }
+    lines.join("\n") + "\n"
+}
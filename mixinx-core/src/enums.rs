@@ -0,0 +1,738 @@
+//! enum的mixin: variant合并、match self补全、enum之间的转换，以及 `#[insert_fields]`
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use std::collections::{HashMap, HashSet};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_quote, Arm, Attribute, Block, Data, DataStruct, DeriveInput, Expr, ExprMatch, Field,
+    Fields, Generics, ImplItem, ImplItemFn, Item, ItemImpl, Pat, PatIdent, Stmt, Type, UnOp,
+    Variant,
+};
+
+use crate::args::InsertMixin;
+use crate::dump::dump_item;
+use crate::insert::{check_insert_allowed, FieldMerger};
+use crate::mixin::{block_fn_key, impl_fn_keys, load_mixin, remove_fns, DeclareOptions, MixinCtx};
+use crate::origin::Ancestry;
+use crate::registry::Registry;
+use crate::replay::Replayed;
+use crate::rewrite::MixinAttrStripper;
+use crate::suggest::no_mixin;
+use crate::util::{has_mixin_flag, is_cfg, key_base, normalize_type};
+use crate::{Error, MixinType};
+
+#[derive(Clone)]
+pub(crate) struct EnumFnOrigin {
+    pub(crate) mixin: Ident,
+    pub(crate) variants: HashSet<String>, //在目标enum里的variant名字
+}
+
+pub(crate) fn enum_origin(
+    enum_mixins: &[EnumMixin],
+    insert_mixin: &InsertMixin,
+) -> Option<EnumFnOrigin> {
+    let enum_mixin = enum_mixins.iter().find(|m| m.name == insert_mixin.name)?;
+    Some(EnumFnOrigin {
+        mixin: enum_mixin.name.clone(),
+        variants: enum_mixin
+            .variants
+            .iter()
+            .map(|(_, target_ident)| target_ident.to_string())
+            .collect(),
+    })
+}
+
+//fn的最后一个表达式是 match self / match *self / match &self 的时候返回这个match
+fn tail_self_match_mut(block: &mut Block) -> Option<&mut ExprMatch> {
+    match block.stmts.last_mut()? {
+        Stmt::Expr(Expr::Match(m), None) if is_self_expr(&m.expr) => Some(m),
+        _ => None,
+    }
+}
+
+fn is_self_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(p) => p.path.is_ident("self"),
+        Expr::Reference(r) => is_self_expr(&r.expr),
+        Expr::Unary(u) if matches!(u.op, UnOp::Deref(_)) => is_self_expr(&u.expr),
+        Expr::Paren(p) => is_self_expr(&p.expr),
+        _ => false,
+    }
+}
+
+//_ 或者不是variant名字的绑定(other => ..)才算兜底的分支，Ping => .. 在use了variant的时候是variant
+fn is_catch_all(arm: &Arm, variants: &HashSet<String>) -> bool {
+    arm.guard.is_none() && is_catch_all_pat(&arm.pat, variants)
+}
+
+fn is_catch_all_pat(pat: &Pat, variants: &HashSet<String>) -> bool {
+    match pat {
+        Pat::Wild(_) => true,
+        Pat::Ident(PatIdent { ident, subpat, .. }) => match subpat {
+            Some((_, subpat)) => is_catch_all_pat(subpat, variants),
+            None => !variants.contains(&ident.to_string()),
+        },
+        Pat::Paren(p) => is_catch_all_pat(&p.pat, variants),
+        Pat::Reference(r) => is_catch_all_pat(&r.pat, variants),
+        Pat::Or(or) => or.cases.iter().any(|case| is_catch_all_pat(case, variants)),
+        _ => false,
+    }
+}
+
+//方法体里没有兜底分支的 match self 有几个，嵌套的fn item里的self不是这个self，不算
+struct IncompleteSelfMatches<'a> {
+    variants: &'a HashSet<String>,
+    count: usize,
+}
+
+impl VisitMut for IncompleteSelfMatches<'_> {
+    fn visit_expr_match_mut(&mut self, m: &mut ExprMatch) {
+        if is_self_expr(&m.expr) && !m.arms.iter().any(|arm| is_catch_all(arm, self.variants)) {
+            self.count += 1;
+        }
+        visit_mut::visit_expr_match_mut(self, m);
+    }
+
+    fn visit_item_mut(&mut self, _: &mut Item) {}
+}
+
+fn count_incomplete_self_matches(block: &mut Block, variants: &HashSet<String>) -> usize {
+    let mut visitor = IncompleteSelfMatches { variants, count: 0 };
+    visitor.visit_block_mut(block);
+    visitor.count
+}
+
+pub(crate) fn is_match_completion(item: &ImplItem) -> bool {
+    match item {
+        ImplItem::Fn(f) => has_mixin_flag(&f.attrs, "arms") || has_mixin_flag(&f.attrs, "fallback"),
+        _ => false,
+    }
+}
+
+//enum继承过来的方法如果是 match self，目标enum新加的variant在原来的match里没有分支，编译不过。
+//这里用overwrite里的 #[mixin(arms)] 补上分支，或者用 #[mixin(fallback)] 的方法体作为 _ => 分支。
+//都没有的话报错，指向没有处理的variant。完整overwrite了的方法不需要处理。
+pub(crate) fn complete_enum_matches(
+    ctx: &mut MixinCtx,
+    fn_origins: &HashMap<String, EnumFnOrigin>,
+    trait_origins: &HashMap<String, EnumFnOrigin>,
+) -> Result<(), Error> {
+    let Data::Enum(en) = &ctx.declaration.as_ref().unwrap().data else {
+        return Ok(());
+    };
+    let variants: Vec<Variant> = en.variants.iter().cloned().collect();
+    let variant_names: HashSet<String> = variants.iter().map(|v| v.ident.to_string()).collect();
+    let target_name = ctx.name.clone();
+
+    let mut completions: HashMap<String, ImplItemFn> = HashMap::new();
+    let mut overwritten: HashSet<String> = HashSet::new();
+    for block in ctx.overwrite_impls.iter() {
+        for item in block.items.iter() {
+            if let ImplItem::Fn(f) = item {
+                if is_match_completion(item) {
+                    completions.insert(f.sig.ident.to_string(), f.clone());
+                } else {
+                    overwritten.insert(f.sig.ident.to_string());
+                }
+            }
+        }
+    }
+
+    let mut errors: Option<syn::Error> = None;
+    let mut push_error = |e: syn::Error| match errors.as_mut() {
+        Some(errors) => errors.combine(e),
+        None => errors = Some(e),
+    };
+
+    for block in ctx.extensions.iter_mut() {
+        let header = ItemImpl {
+            items: Vec::new(),
+            ..block.clone()
+        };
+        for item in block.items.iter_mut() {
+            let ImplItem::Fn(impl_fn) = item else {
+                continue;
+            };
+            let Some(origin) = fn_origins.get(&block_fn_key(&header, impl_fn)) else {
+                continue;
+            };
+            let fn_name = impl_fn.sig.ident.to_string();
+            if overwritten.contains(&fn_name) {
+                continue;
+            }
+            let missing: Vec<&Variant> = variants
+                .iter()
+                .filter(|v| !origin.variants.contains(&v.ident.to_string()))
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+            //只有最后的 match self 能补分支，其他地方没有兜底的 match self 只能整个overwrite
+            let incomplete = count_incomplete_self_matches(&mut impl_fn.block, &variant_names);
+            let tail_complete = tail_self_match_mut(&mut impl_fn.block)
+                .is_none_or(|m| m.arms.iter().any(|arm| is_catch_all(arm, &variant_names)));
+            if incomplete > usize::from(!tail_complete) {
+                for variant in missing {
+                    push_error(syn::Error::new_spanned(
+                        &variant.ident,
+                        format!(
+                            "variant `{}::{}` is not handled by a `match self` inside `{}`, which is inherited from `{}`; \
+                             only the `match self` the method ends with can be completed, overwrite the whole method \
+                             with `#[overwrite] impl {} {{ fn {}(..) {{ .. }} }}`",
+                            target_name, variant.ident, fn_name, origin.mixin, target_name, fn_name
+                        ),
+                    ));
+                }
+                continue;
+            }
+            if tail_complete {
+                continue;
+            }
+            let expr_match = tail_self_match_mut(&mut impl_fn.block).unwrap();
+            for arm in expr_match.arms.iter_mut() {
+                arm.comma.get_or_insert_with(Default::default);
+            }
+            match completions.get_mut(&fn_name) {
+                Some(completion) if has_mixin_flag(&completion.attrs, "arms") => {
+                    let Some(extra) = tail_self_match_mut(&mut completion.block) else {
+                        push_error(syn::Error::new_spanned(
+                            &completion.sig,
+                            format!(
+                                "#[mixin(arms)] on `{}` expects the body to be `match self {{ .. }}`",
+                                fn_name
+                            ),
+                        ));
+                        continue;
+                    };
+                    for arm in extra.arms.iter() {
+                        let mut arm = arm.clone();
+                        arm.comma.get_or_insert_with(Default::default);
+                        expr_match.arms.push(arm);
+                    }
+                    //补上的分支仍然不完整的话交给rustc报错
+                }
+                Some(completion) => {
+                    let fallback = &completion.block;
+                    expr_match.arms.push(parse_quote! {
+                        #[allow(unreachable_patterns)]
+                        _ => #fallback,
+                    });
+                }
+                None => {
+                    for variant in missing {
+                        push_error(syn::Error::new_spanned(
+                            &variant.ident,
+                            format!(
+                                "variant `{}::{}` is not handled by `{}`, which is inherited from `{}` and matches on self; \
+                                 add `#[overwrite] impl {} {{ #[mixin(arms)] fn {}(..) {{ match self {{ .. }} }} }}` \
+                                 or a `#[mixin(fallback)] fn {}(..)`, or overwrite the whole method",
+                                target_name, variant.ident, fn_name, origin.mixin, target_name, fn_name, fn_name
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    //trait impl没办法单独补分支，只能整个overwrite
+    let over_traits: HashSet<&str> = ctx.over_traits.keys().map(|k| key_base(k)).collect();
+    for (trait_name, trait_impl) in ctx.impl_traits.iter_mut() {
+        let Some(origin) = trait_origins.get(trait_name) else {
+            continue;
+        };
+        if over_traits.contains(key_base(trait_name)) {
+            continue;
+        }
+        let missing: Vec<&Variant> = variants
+            .iter()
+            .filter(|v| !origin.variants.contains(&v.ident.to_string()))
+            .collect();
+        if missing.is_empty() {
+            continue;
+        }
+        for item in trait_impl.items.iter_mut() {
+            let ImplItem::Fn(impl_fn) = item else {
+                continue;
+            };
+            if count_incomplete_self_matches(&mut impl_fn.block, &variant_names) == 0 {
+                continue;
+            }
+            for variant in missing.iter() {
+                push_error(syn::Error::new_spanned(
+                    &variant.ident,
+                    format!(
+                        "variant `{}::{}` is not handled by `{}` in the impl of `{}` inherited from `{}`; \
+                         overwrite that trait impl for `{}`",
+                        target_name,
+                        variant.ident,
+                        impl_fn.sig.ident,
+                        key_base(trait_name),
+                        origin.mixin,
+                        target_name
+                    ),
+                ));
+            }
+        }
+    }
+
+    match errors {
+        Some(errors) => Err(errors.into()),
+        None => Ok(()),
+    }
+}
+
+//同名variant是否一样: 去掉属性、类型normalize之后的字段以及discriminant
+pub(crate) fn same_variant_shape(a: &Variant, b: &Variant) -> bool {
+    let fields = |variant: &Variant| {
+        let mut fields = variant.fields.clone();
+        for field in fields.iter_mut() {
+            field.attrs.clear();
+            field.ty = normalize_type(&field.ty);
+        }
+        fields
+    };
+    let discriminant = |variant: &Variant| variant.discriminant.as_ref().map(|(_, e)| e.clone());
+    a.ident == b.ident && fields(a) == fields(b) && discriminant(a) == discriminant(b)
+}
+
+//错误信息里显示的variant
+pub(crate) fn variant_shape(variant: &Variant) -> String {
+    let mut fields = variant.fields.clone();
+    for field in fields.iter_mut() {
+        field.attrs.clear();
+    }
+    let ident = &variant.ident;
+    let discriminant = variant
+        .discriminant
+        .as_ref()
+        .map(|(_, expr)| quote! { = #expr });
+    quote! { #ident #fields #discriminant }.to_string()
+}
+
+//插入到enum里的另一个enum，variants是 (mixin里的variant, 目标enum里对应的variant名字)
+pub(crate) struct EnumMixin {
+    pub(crate) name: Ident,
+    pub(crate) generics: Generics,
+    pub(crate) variants: Vec<(Variant, Ident)>,
+    pub(crate) is_abstract: bool, //abstract的enum没有输出，不能生成转换
+}
+
+//variant的pattern，同时也可以用来构造: (__f0, __f1) 或者 { a, b }，以及绑定的名字
+fn variant_bindings(variant: &Variant) -> (TokenStream, Vec<Ident>) {
+    match &variant.fields {
+        Fields::Named(named) => {
+            let names: Vec<Ident> = named.named.iter().filter_map(|f| f.ident.clone()).collect();
+            (quote! { { #(#names),* } }, names)
+        }
+        Fields::Unnamed(unnamed) => {
+            let names: Vec<Ident> = (0..unnamed.unnamed.len())
+                .map(|i| format_ident!("__f{}", i))
+                .collect();
+            (quote! { ( #(#names),* ) }, names)
+        }
+        Fields::Unit => (quote! {}, Vec::new()),
+    }
+}
+
+//as_register(&self) -> Option<&String>，多个字段的返回引用的tuple，没有字段的variant不生成
+fn gen_variant_accessor(variant: &Variant, target_ident: &Ident) -> Option<TokenStream> {
+    let (bind, names) = variant_bindings(variant);
+    let types: Vec<&Type> = variant.fields.iter().map(|f| &f.ty).collect();
+    let (ty, value) = match types.as_slice() {
+        [] => return None,
+        [ty] => (quote! { &#ty }, quote! { #(#names)* }),
+        _ => (quote! { ( #(&#types),* ) }, quote! { ( #(#names),* ) }),
+    };
+    let cfgs = variant.attrs.iter().filter(|a| is_cfg(a));
+    let as_fn = format_ident!("as_{}", target_ident.to_string().to_case(Case::Snake));
+    Some(quote! {
+        #(#cfgs)*
+        #[allow(unreachable_patterns)]
+        pub fn #as_fn(&self) -> ::core::option::Option<#ty> {
+            match self {
+                Self::#target_ident #bind => Some(#value),
+                _ => None,
+            }
+        }
+    })
+}
+
+pub(crate) fn gen_enum_conversions(
+    target: &DeriveInput,
+    mixin: &EnumMixin,
+) -> (ItemImpl, ItemImpl, TokenStream) {
+    let name = &target.ident;
+    let mixin_name = &mixin.name;
+    let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+    let (_, mixin_generics, _) = mixin.generics.split_for_impl();
+
+    let mut into_arms = Vec::new();
+    let mut from_arms = Vec::new();
+    let mut is_arms = Vec::new();
+    let mut accessors = Vec::new();
+    for (variant, target_ident) in mixin.variants.iter() {
+        let ident = &variant.ident;
+        let cfgs: Vec<&Attribute> = variant.attrs.iter().filter(|a| is_cfg(a)).collect();
+        let (bind, _) = variant_bindings(variant);
+        into_arms
+            .push(quote! { #(#cfgs)* #mixin_name::#ident #bind => Self::#target_ident #bind, });
+        from_arms.push(quote! { #(#cfgs)* #name::#target_ident #bind => Ok(Self::#ident #bind), });
+        is_arms.push(quote! { #(#cfgs)* Self::#target_ident { .. } => true, });
+        accessors.extend(gen_variant_accessor(variant, target_ident));
+    }
+
+    let snake = mixin_name.to_string().to_case(Case::Snake);
+    let is_fn = format_ident!("is_{}", snake);
+
+    let from_impl: ItemImpl = parse_quote! {
+        impl #impl_generics ::core::convert::From<#mixin_name #mixin_generics> for #name #ty_generics #where_clause {
+            fn from(value: #mixin_name #mixin_generics) -> Self {
+                match value {
+                    #(#into_arms)*
+                }
+            }
+        }
+    };
+    let helpers: ItemImpl = parse_quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #[allow(unreachable_patterns)]
+            pub fn #is_fn(&self) -> bool {
+                match self {
+                    #(#is_arms)*
+                    _ => false,
+                }
+            }
+            #(#accessors)*
+        }
+    };
+    let try_from_impl = quote! {
+        impl #impl_generics ::core::convert::TryFrom<#name #ty_generics> for #mixin_name #mixin_generics #where_clause {
+            type Error = #name #ty_generics;
+            #[allow(unreachable_patterns)]
+            fn try_from(value: #name #ty_generics) -> ::core::result::Result<Self, Self::Error> {
+                match value {
+                    #(#from_arms)*
+                    other => Err(other),
+                }
+            }
+        }
+    };
+    (from_impl, helpers, try_from_impl)
+}
+
+//nest模式的转换: From<DeviceMessage>、TryFrom<APMSG>，is_device_message/as_device_message返回引用
+pub(crate) fn gen_nest_conversions(
+    target: &DeriveInput,
+    mixin: &Ident,
+    nest: &Ident,
+) -> (ItemImpl, ItemImpl, TokenStream) {
+    let name = &target.ident;
+    let (impl_generics, ty_generics, where_clause) = target.generics.split_for_impl();
+    let snake = mixin.to_string().to_case(Case::Snake);
+    let is_fn = format_ident!("is_{}", snake);
+    let as_fn = format_ident!("as_{}", snake);
+
+    let from_impl: ItemImpl = parse_quote! {
+        impl #impl_generics ::core::convert::From<#mixin> for #name #ty_generics #where_clause {
+            fn from(value: #mixin) -> Self {
+                Self::#nest(value)
+            }
+        }
+    };
+    let helpers: ItemImpl = parse_quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub fn #is_fn(&self) -> bool {
+                matches!(self, Self::#nest(_))
+            }
+            #[allow(unreachable_patterns)]
+            pub fn #as_fn(&self) -> ::core::option::Option<&#mixin> {
+                match self {
+                    Self::#nest(inner) => Some(inner),
+                    _ => None,
+                }
+            }
+        }
+    };
+    let try_from_impl = quote! {
+        impl #impl_generics ::core::convert::TryFrom<#name #ty_generics> for #mixin #where_clause {
+            type Error = #name #ty_generics;
+            #[allow(unreachable_patterns)]
+            fn try_from(value: #name #ty_generics) -> ::core::result::Result<Self, Self::Error> {
+                match value {
+                    #name::#nest(inner) => Ok(inner),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+    (from_impl, helpers, try_from_impl)
+}
+
+//#[insert_fields(EventMeta)] enum Event { Created { .. }, Deleted { .. } }
+//把struct的字段加到enum的每一个struct variant里，并且生成 event.timestamp()、event.get_event_meta()
+pub(crate) fn insert_fields_impl(
+    registry: &mut Registry,
+    args: TokenStream,
+    input: TokenStream,
+    replayed: Option<&Replayed>,
+) -> Result<TokenStream, Error> {
+    let mut the_enum: DeriveInput = syn::parse2(input)?;
+    let args = syn::parse::Parser::parse2(Punctuated::<Ident, Comma>::parse_terminated, args)?;
+    //insert会把同名的mixin当作是overwrite留下的，放在insert后面，等variant都合并完再加字段
+    if let Some(attr) = the_enum.attrs.iter().find(|a| a.path().is_ident("insert")) {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "place #[insert_fields(..)] after #[insert(..)]",
+        )
+        .into());
+    }
+    let enum_name = the_enum.ident.clone();
+    if replayed.is_none() {
+        registry.check_undeclared("insert_fields", &enum_name)?;
+    }
+    let Data::Enum(en) = &mut the_enum.data else {
+        return Err(
+            syn::Error::new_spanned(&enum_name, "#[insert_fields] only applies to enums").into(),
+        );
+    };
+    //没有variant的话访问函数里是 match self {}，&self上编译不过
+    if en.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &enum_name,
+            format!(
+                "`{}` has no variants to receive the fields of {}",
+                enum_name,
+                args.iter()
+                    .map(|a| format!("`{}`", a))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+        .into());
+    }
+
+    let data = &mut registry.mixins;
+    let mut inserted = Vec::new();
+    let mut allowed = TokenStream::new();
+    for arg in args.iter() {
+        let mixin = data.get(&arg.to_string()).ok_or_else(|| {
+            no_mixin(
+                "insert_fields",
+                arg,
+                &arg.to_string(),
+                data,
+                &registry.failed,
+            )
+        })?;
+        let mixin_ctx = load_mixin(mixin, arg)?;
+        allowed.extend(check_insert_allowed(&mixin_ctx, arg)?);
+        let Some(declaration) = mixin_ctx.declaration.as_ref() else {
+            return Err(no_mixin(
+                "insert_fields",
+                arg,
+                &arg.to_string(),
+                data,
+                &registry.failed,
+            )
+            .into());
+        };
+        let fields = match &declaration.data {
+            Data::Struct(DataStruct {
+                fields: Fields::Named(named),
+                ..
+            }) if declaration.generics.params.is_empty() => named.named.clone(),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    arg,
+                    format!(
+                        "`{}` must be a struct with named fields and no generics",
+                        arg
+                    ),
+                )
+                .into())
+            }
+        };
+
+        let mut errors: Option<syn::Error> = None;
+        for variant in en.variants.iter_mut() {
+            let Fields::Named(named) = &mut variant.fields else {
+                let error = syn::Error::new_spanned(
+                    &variant.ident,
+                    format!(
+                        "`{}::{}` must be a struct variant to receive the fields of `{}`",
+                        enum_name, variant.ident, arg
+                    ),
+                );
+                match errors.as_mut() {
+                    Some(errors) => errors.combine(error),
+                    None => errors = Some(error),
+                }
+                continue;
+            };
+            //和struct的字段合并一样，已经有的跳过
+            let mut merger = FieldMerger::new(&variant.ident, &named.named);
+            for field in fields.iter() {
+                if let Some(mut field) = merger.merge(field, arg)? {
+                    field.vis = syn::Visibility::Inherited; //variant的字段不能有pub
+                    named.named.push(field);
+                }
+            }
+        }
+        if let Some(errors) = errors {
+            return Err(errors.into());
+        }
+        inserted.push((arg, fields));
+    }
+    //所有字段都加完了再生成访问函数，cfg和variant都是最终的
+    let mut accessors: Vec<ItemImpl> = inserted
+        .iter()
+        .map(|(arg, fields)| gen_variant_field_accessors(&the_enum, arg, fields))
+        .collect();
+
+    //enum登记到全局变量里，别的enum可以继续insert它
+    let before = match replayed {
+        Some(replayed) => replayed.before.as_ref(),
+        None => data.get(&enum_name.to_string()),
+    };
+    let mut mixin_ctx = match before {
+        Some(mixin) => load_mixin(mixin, &enum_name)?,
+        None => MixinCtx {
+            name: enum_name.clone(),
+            mixin_type: MixinType::Enum,
+            declaration: None,
+            extensions: Vec::new(),
+            overwrite_impls: Vec::new(),
+            impl_traits: HashMap::new(),
+            over_traits: HashMap::new(),
+            options: DeclareOptions::default(),
+            required: Vec::new(),
+            ancestry: Ancestry::default(),
+        },
+    };
+    //前面只有overwrite的话(没有insert)，overwrite的块在这里输出，同名的访问函数以overwrite的为准。
+    //insert过的话insert已经输出了
+    let overwrites = if mixin_ctx.declaration.is_none() {
+        let blocks = mixin_ctx.overwrite_impls.clone();
+        let overwrite_names: HashSet<String> = blocks
+            .iter()
+            .flat_map(impl_fn_keys)
+            .map(|k| key_base(&k).to_string())
+            .collect();
+        remove_fns(&mut accessors, |k| overwrite_names.contains(key_base(k)));
+        let traits: Vec<(String, ItemImpl)> = mixin_ctx
+            .over_traits
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        (blocks, traits)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    mixin_ctx.mixin_type = MixinType::Enum;
+    mixin_ctx.declaration = Some(the_enum.clone());
+    for (arg, fields) in inserted.iter() {
+        if !mixin_ctx.ancestry.parents.contains(&arg.to_string()) {
+            mixin_ctx.ancestry.parents.push(arg.to_string());
+        }
+        for ident in fields.iter().filter_map(|f| f.ident.as_ref()) {
+            mixin_ctx
+                .ancestry
+                .field_origins
+                .entry(ident.to_string())
+                .or_insert_with(|| arg.to_string());
+        }
+    }
+    let mut output = the_enum.to_token_stream();
+    output.extend(allowed);
+    for block in accessors {
+        mixin_ctx.add_extension(&block);
+        output.extend(block.to_token_stream());
+    }
+    let (overwrite_blocks, overwrite_traits) = overwrites;
+    for mut block in overwrite_blocks {
+        mixin_ctx.add_extension(&block);
+        MixinAttrStripper.visit_item_impl_mut(&mut block);
+        output.extend(block.to_token_stream());
+    }
+    for (trait_name, mut trait_impl) in overwrite_traits {
+        mixin_ctx.impl_traits.insert(trait_name, trait_impl.clone());
+        MixinAttrStripper.visit_item_impl_mut(&mut trait_impl);
+        output.extend(trait_impl.to_token_stream());
+    }
+    dump_item(&mixin_ctx, &output, false);
+    if replayed.is_none() {
+        data.insert(enum_name.to_string(), (&mixin_ctx).into());
+    }
+    Ok(output)
+}
+
+//每个字段一个 &T 的访问函数，再加一个get_xxx返回整个struct
+fn gen_variant_field_accessors(
+    the_enum: &DeriveInput,
+    mixin: &Ident,
+    fields: &Punctuated<Field, Comma>,
+) -> ItemImpl {
+    let name = &the_enum.ident;
+    let variants: Vec<(&Ident, Vec<&Attribute>)> = match &the_enum.data {
+        Data::Enum(en) => en
+            .variants
+            .iter()
+            .map(|v| (&v.ident, v.attrs.iter().filter(|a| is_cfg(a)).collect()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let fds: Vec<(&Ident, &Type, Vec<&Attribute>)> = fields
+        .iter()
+        .map(|f| {
+            (
+                f.ident.as_ref().unwrap(),
+                &f.ty,
+                f.attrs.iter().filter(|a| is_cfg(a)).collect(),
+            )
+        })
+        .collect();
+
+    let field_fns = fds.iter().map(|(field, ty, field_cfgs)| {
+        let arms = variants.iter().map(|(variant, cfgs)| {
+            quote! { #(#cfgs)* Self::#variant { #field, .. } => #field, }
+        });
+        quote! {
+            #(#field_cfgs)*
+            pub fn #field(&self) -> &#ty {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    });
+
+    let get_fn_name = format_ident!("get_{}", mixin.to_string().to_case(Case::Snake));
+    let pats: Vec<TokenStream> = fds
+        .iter()
+        .map(|(field, _, cfgs)| quote! { #(#cfgs)* #field })
+        .collect();
+    let inits: Vec<TokenStream> = fds
+        .iter()
+        .map(|(field, _, cfgs)| quote! { #(#cfgs)* #field: #field.clone() })
+        .collect();
+    let get_arms = variants.iter().map(|(variant, cfgs)| {
+        quote! { #(#cfgs)* Self::#variant { #(#pats,)* .. } => #mixin { #(#inits,)* }, }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = the_enum.generics.split_for_impl();
+    parse_quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#field_fns)*
+
+            pub fn #get_fn_name(&self) -> #mixin {
+                match self {
+                    #(#get_arms)*
+                }
+            }
+        }
+    }
+}
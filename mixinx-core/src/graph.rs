@@ -0,0 +1,243 @@
+//! `graph!()`: 登记过的类型和它们的继承关系，输出成json或者Graphviz
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::ToTokens;
+use std::collections::HashMap;
+use syn::{Data, Field};
+
+use crate::mixin::{impl_fn_keys, MixinCtx};
+use crate::registry::Registry;
+use crate::util::{display_tokens, display_type};
+use crate::MixinType;
+
+impl Registry {
+    /// `graph!()`/`graph!(json)`/`graph!(dot)`: 把 [`graph_json`](Self::graph_json) 或者
+    /// [`graph_dot`](Self::graph_dot) 的结果输出成一个字符串字面量
+    pub fn graph(&self, args: TokenStream) -> TokenStream {
+        let format = match syn::parse2::<Option<Ident>>(args.clone()) {
+            Ok(None) => "json".to_string(),
+            Ok(Some(format)) => format.to_string(),
+            Err(_) => String::new(),
+        };
+        let text = match format.as_str() {
+            "json" => self.graph_json(),
+            "dot" => self.graph_dot(),
+            _ => {
+                return syn::Error::new_spanned(
+                    args,
+                    "expected `graph!()`, `graph!(json)` or `graph!(dot)`",
+                )
+                .to_compile_error()
+            }
+        };
+        syn::LitStr::new(&text, Span::call_site()).to_token_stream()
+    }
+
+    /// 登记过的所有类型，按名字排序: 字段(连同类型)、方法、trait以及它们是从哪个mixin来的，overwrite，
+    /// insert的父类型和线性化之后的祖先。`{"mixins": [{"name": "Student", "kind": "struct", ..}]}`
+    pub fn graph_json(&self) -> String {
+        let nodes = self.graph_nodes();
+        serde_json::to_string_pretty(&serde_json::json!({ "mixins": nodes })).unwrap_or_default()
+    }
+
+    /// 和 [`graph_json`](Self::graph_json) 一样的内容，Graphviz的格式，边从类型指向它insert的类型
+    pub fn graph_dot(&self) -> String {
+        let nodes = self.graph_nodes();
+        let mut lines = vec![
+            "digraph mixinx {".to_string(),
+            "    rankdir=BT;".to_string(),
+            "    node [shape=record, fontname=\"monospace\"];".to_string(),
+        ];
+        lines.extend(nodes.iter().map(GraphNode::dot_node));
+        for node in nodes.iter() {
+            for parent in node.parents.iter() {
+                lines.push(format!("    \"{}\" -> \"{}\";", node.name, parent));
+            }
+        }
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn graph_nodes(&self) -> Vec<GraphNode> {
+        let mut names: Vec<&String> = self.mixins.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| MixinCtx::try_from(&self.mixins[name]).ok())
+            .map(|mixin| GraphNode::new(&mixin))
+            .collect()
+    }
+}
+
+//graph!() 输出的一个mixin: 字段、方法、trait以及它们的来源，overwrite，insert的边
+#[derive(serde::Serialize)]
+pub(crate) struct GraphNode {
+    pub(crate) name: String,
+    kind: &'static str,
+    #[serde(rename = "abstract")]
+    is_abstract: bool,
+    parents: Vec<String>,
+    pub(crate) ancestors: Vec<String>,
+    pub(crate) fields: Vec<GraphMember>,
+    variants: Vec<String>,
+    pub(crate) methods: Vec<GraphMember>,
+    pub(crate) traits: Vec<GraphMember>,
+    overwrites: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct GraphMember {
+    pub(crate) name: String,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    ty: Option<String>,
+    pub(crate) from: String,
+}
+
+impl GraphNode {
+    pub(crate) fn new(mixin: &MixinCtx) -> Self {
+        let name = mixin.name.to_string();
+        let member = |label: String,
+                      ty: Option<String>,
+                      origins: &HashMap<String, String>,
+                      key: &str| GraphMember {
+            name: label,
+            ty,
+            from: origins.get(key).cloned().unwrap_or_else(|| name.clone()),
+        };
+        let field_origins = &mixin.ancestry.field_origins;
+        let ty = |field: &Field| Some(display_type(&field.ty));
+        let (kind, fields, variants) = match mixin.declaration.as_ref().map(|d| &d.data) {
+            Some(Data::Struct(st)) => {
+                let fields = st
+                    .fields
+                    .iter()
+                    .filter_map(|f| {
+                        f.ident.as_ref().map(|ident| {
+                            member(ident.to_string(), ty(f), field_origins, &ident.to_string())
+                        })
+                    })
+                    .collect();
+                ("struct", fields, Vec::new())
+            }
+            //insert_fields加到variant上的字段，名字写成 Variant::field
+            Some(Data::Enum(en)) => {
+                let fields = en
+                    .variants
+                    .iter()
+                    .flat_map(|v| v.fields.iter().map(move |f| (v, f)))
+                    .filter_map(|(v, f)| {
+                        let ident = f.ident.as_ref()?;
+                        Some(member(
+                            format!("{}::{}", v.ident, ident),
+                            ty(f),
+                            field_origins,
+                            &ident.to_string(),
+                        ))
+                    })
+                    .collect();
+                (
+                    "enum",
+                    fields,
+                    en.variants.iter().map(|v| v.ident.to_string()).collect(),
+                )
+            }
+            _ if mixin.mixin_type == MixinType::Enum => ("enum", Vec::new(), Vec::new()),
+            _ if mixin.mixin_type == MixinType::Struct => ("struct", Vec::new(), Vec::new()),
+            _ => ("unknown", Vec::new(), Vec::new()),
+        };
+        let methods = mixin
+            .extensions
+            .iter()
+            .flat_map(impl_fn_keys)
+            .map(|key| member(display_key(&key), None, &mixin.ancestry.fn_origins, &key))
+            .collect();
+        let mut traits: Vec<GraphMember> = mixin
+            .impl_traits
+            .keys()
+            .map(|key| member(display_key(key), None, &mixin.ancestry.trait_origins, key))
+            .collect();
+        traits.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut overwrites: Vec<String> = mixin
+            .overwrite_impls
+            .iter()
+            .flat_map(impl_fn_keys)
+            .map(|key| display_key(&key))
+            .collect();
+        let mut over_traits: Vec<String> = mixin
+            .over_traits
+            .keys()
+            .map(|key| display_key(key))
+            .collect();
+        over_traits.sort();
+        overwrites.extend(over_traits);
+        GraphNode {
+            name: name.clone(),
+            kind,
+            is_abstract: mixin.options.is_abstract,
+            parents: mixin.ancestry.parents.clone(),
+            ancestors: mixin.ancestry.linearization.clone(),
+            fields,
+            variants,
+            methods,
+            traits,
+            overwrites,
+        }
+    }
+
+    //record形状的节点: 名字 | 字段 | 方法 | trait，继承来的后面注明来源
+    fn dot_node(&self) -> String {
+        let rows = |members: &[GraphMember], suffix: &str| -> String {
+            members
+                .iter()
+                .map(|m| {
+                    let mut row = m.name.clone() + suffix;
+                    if let Some(ty) = m.ty.as_ref() {
+                        row.push_str(&format!(": {}", ty));
+                    }
+                    if m.from != self.name {
+                        row.push_str(&format!(" ({})", m.from));
+                    }
+                    dot_escape(&row) + "\\l"
+                })
+                .collect()
+        };
+        let title = if self.is_abstract {
+            format!("{} (abstract)", self.name)
+        } else {
+            self.name.clone()
+        };
+        format!(
+            "    \"{}\" [label=\"{{{}|{}|{}|{}}}\"];",
+            self.name,
+            dot_escape(&title),
+            rows(&self.fields, ""),
+            rows(&self.methods, "()"),
+            rows(&self.traits, "")
+        )
+    }
+}
+
+//record的label里 {}|<> 和引号有特殊含义
+fn dot_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '"' | '{' | '}' | '|' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+//"std :: fmt :: Display#cfg(feature = \"x\")" => "std::fmt::Display #[cfg(feature = \"x\")]"
+fn display_key(key: &str) -> String {
+    let (base, cfg) = key
+        .split_once('#')
+        .map_or((key, None), |(base, cfg)| (base, Some(cfg)));
+    let base = base
+        .parse::<TokenStream>()
+        .map_or_else(|_| base.to_string(), display_tokens);
+    match cfg {
+        Some(cfg) => format!("{} #[{}]", base, cfg),
+        None => base,
+    }
+}
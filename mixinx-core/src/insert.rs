@@ -0,0 +1,1432 @@
+//! `#[insert(..)]`: 按复制、embed、nest三种方式把mixin合并到目标类型上
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use std::collections::{HashMap, HashSet};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::visit_mut::VisitMut;
+use syn::{
+    parse_quote, Data, DeriveInput, Field, Fields, ForeignItemFn, ImplItem, ItemImpl, Type, Variant,
+};
+
+use crate::args::{FieldPosition, InsertArgs, InsertMixin, SerdeMode};
+use crate::ctor::{
+    gen_chained_ctor, gen_chained_default, is_ctor, is_default_impl, CtorKind, MixedCtor,
+};
+use crate::declare::{gen_get_set_impls, mixin_type_of};
+use crate::delegate::{
+    forward_trait_impl, gen_delegate, gen_embed_impls, overwrites_fn, overwrites_trait,
+    unforwardable, DelegateVia,
+};
+use crate::dump::dump_item;
+use crate::enums::{
+    complete_enum_matches, enum_origin, gen_enum_conversions, gen_nest_conversions,
+    is_match_completion, same_variant_shape, variant_shape, EnumFnOrigin, EnumMixin,
+};
+use crate::layout::{gen_layout_assertions, gen_prefix_casts};
+use crate::mixin::{
+    block_fn_key, impl_fn_keys, load_mixin, remove_fns, DeclareOptions, Mixin, MixinCtx,
+};
+use crate::origin::{c3_linearize, is_ancestor, pick_origin, Ancestry, OriginConflict, OriginPick};
+use crate::registry::Registry;
+use crate::replay::Replayed;
+use crate::rewrite::{generic_param_name, retarget_impl, trait_key, SelfTypeRewriter};
+use crate::suggest::no_mixin;
+use crate::util::{
+    cfg_predicate, derives_serde, display_tokens, display_type, is_cfg, is_serde_attr, key_base,
+    normalize_type,
+};
+use crate::{Error, MixinType};
+
+//insert的时候从各个mixin收集起来的东西，所有mixin都处理完了再合并到目标类型上
+#[derive(Default)]
+struct Collected<'a> {
+    //如果mixin_type 是struct，需要混入字段。
+    fields: Vec<(Punctuated<Field, Comma>, FieldPosition, &'a Ident)>,
+    variants: Vec<(Variant, Ident)>,
+    ctors: Vec<MixedCtor>,
+    enum_mixins: Vec<EnumMixin>,
+    //从enum mixin继承过来的fn/trait，以及这个mixin有哪些variant，后面用来检查match self是否完整
+    enum_fn_origins: HashMap<String, EnumFnOrigin>,
+    enum_trait_origins: HashMap<String, EnumFnOrigin>,
+    nest_mixins: Vec<(Ident, Ident)>, //(mixin, 包装它的variant)
+    layout_checks: Vec<(&'a InsertMixin, DeriveInput)>, //check_layout的mixin以及它的声明
+    required_fns: Vec<(Ident, ForeignItemFn)>, //(mixin, 目标类型必须实现的方法)
+    extra_stream: TokenStream,        //不放到registry里，直接输出的代码
+    //继承过来的fn/trait是哪个类型定义的，菱形继承时用来决定留哪一份
+    fn_origins: HashMap<String, String>,
+    trait_origins: HashMap<String, String>,
+    origin_conflicts: Vec<OriginConflict>,
+}
+
+//目标类型自己overwrite的fn/trait，带 #[mixin(arms)]/#[mixin(fallback)] 的只是用来补全match的，不算
+struct Overwrites {
+    impls: Vec<ItemImpl>,
+    fn_names: HashSet<String>,
+    trait_names: HashSet<String>,
+}
+
+impl Overwrites {
+    fn of(the_struct_mixin_ctx: &MixinCtx) -> Self {
+        let impls: Vec<ItemImpl> = the_struct_mixin_ctx
+            .overwrite_impls
+            .iter()
+            .cloned()
+            .filter_map(|mut block| {
+                block.items.retain(|item| !is_match_completion(item));
+                (!block.items.is_empty()).then_some(block)
+            })
+            .collect();
+        let fn_names = impls
+            .iter()
+            .flat_map(impl_fn_keys)
+            .map(|k| key_base(&k).to_string())
+            .collect();
+        let trait_names = the_struct_mixin_ctx
+            .over_traits
+            .keys()
+            .map(|k| key_base(k).to_string())
+            .collect();
+        Overwrites {
+            impls,
+            fn_names,
+            trait_names,
+        }
+    }
+}
+
+//如果有多个impl Struct，是可以的
+//如果有多个impl Trait for Struct是不可以有相同大的Trait出现的。
+//所以impl Trait for Struct需要记录Trait的信息。
+//因为insert的Struct也会建立对应的Mixin，但是如果有overwrite的 impl的话，其mixin是在inser之前建立的。
+//需要解析出impl中具体的函数吗？
+//因为overwrite的存在，所以insert中的流程应该是
+//1、从全局变量中获取the_struct的mixin，如果有，说明其中有overwrite，但是这个overwrite的trait
+
+pub(crate) fn insert_impl(
+    registry: &mut Registry,
+    args: TokenStream,
+    input: TokenStream,
+    replayed: Option<&Replayed>,
+) -> Result<TokenStream, Error> {
+    let data = &registry.mixins;
+    let the_struct: DeriveInput = syn::parse2(input.clone())?;
+    let the_struct_name = the_struct.ident.to_string();
+    if replayed.is_none() {
+        registry.check_undeclared("insert", &the_struct.ident)?;
+    }
+    //    dbg!(&the_struct_name);
+    //如果这里有值，说明有overwrite的处理。回放过的话registry里已经是insert之后的结果，用回放之前的
+    let the_struct_mixin = match replayed {
+        Some(replayed) => replayed.before.as_ref(),
+        None => data.get(&the_struct_name),
+    };
+    //dbg!(&the_struct_mixin);
+    let mixin_type = mixin_type_of(&the_struct)?;
+    let mut the_struct_mixin_ctx = if let Some(mixin) = the_struct_mixin {
+        let mut ctx = load_mixin(mixin, &the_struct.ident)?;
+        ctx.declaration = Some(the_struct);
+        ctx.mixin_type = mixin_type; //overwrite的时候还不知道类型
+        ctx
+    } else {
+        MixinCtx {
+            name: the_struct.ident.clone(),
+            mixin_type,
+            declaration: Some(the_struct),
+            extensions: Vec::new(),
+            overwrite_impls: Vec::new(),
+            impl_traits: HashMap::new(),
+            over_traits: HashMap::new(),
+            options: DeclareOptions::default(),
+            required: Vec::new(),
+            ancestry: Ancestry::default(),
+        }
+    };
+
+    // Get names of mixins to append
+    //需要mixin的结构名称是从参数来的，如果是跨包的mixin，这里是不是需要用包的全路径才行？
+    let insert_args: InsertArgs = syn::parse2(args)?;
+    let mut collected = Collected::default();
+    for insert_mixin in insert_args.mixins.iter() {
+        let mixin_name = insert_mixin.name.to_string();
+        let mixin = data.get(&mixin_name).ok_or_else(|| {
+            no_mixin(
+                "insert",
+                &insert_mixin.name,
+                &mixin_name,
+                data,
+                &registry.failed,
+            )
+        })?; //根据mixin_name从全局变量中找到对应的mixin
+        if mixin_name == the_struct_name || mixin.ancestry.linearization.contains(&the_struct_name)
+        {
+            return Err(syn::Error::new_spanned(
+                &insert_mixin.name,
+                format!(
+                    "inserting `{}` into `{}` makes a cycle: `{}` already inherits from `{}`",
+                    mixin_name, the_struct_name, mixin_name, the_struct_name
+                ),
+            )
+            .into());
+        }
+
+        let extend_mixin_ctx = load_mixin(mixin, &insert_mixin.name)?;
+        //只有overwrite过、还没有insert的类型没有声明
+        if extend_mixin_ctx.declaration.is_none() {
+            return Err(syn::Error::new_spanned(
+                &insert_mixin.name,
+                format!(
+                    "`{}` only has #[overwrite] blocks so far; insert it after its declaration",
+                    mixin_name
+                ),
+            )
+            .into());
+        }
+        collected
+            .extra_stream
+            .extend(check_insert_allowed(&extend_mixin_ctx, &insert_mixin.name)?);
+        //abstract的mixin没有输出类型本身，所有需要用到这个类型的方式都不行
+        let is_abstract = extend_mixin_ctx.options.is_abstract;
+        if is_abstract
+            && (insert_mixin.embed.is_some()
+                || insert_mixin.nest.is_some()
+                || insert_mixin.check_layout)
+        {
+            return Err(syn::Error::new_spanned(
+                &insert_mixin.name,
+                format!(
+                    "`{}` is abstract, its fields can only be copied",
+                    mixin_name
+                ),
+            )
+            .into());
+        }
+        if let Some(field) = insert_mixin.embed.as_ref() {
+            insert_embed(
+                &mut the_struct_mixin_ctx,
+                &mut collected,
+                insert_mixin,
+                &extend_mixin_ctx,
+                field,
+            )?;
+        } else if let Some(nest) = insert_mixin.nest.as_ref() {
+            insert_nest(
+                &mut the_struct_mixin_ctx,
+                &mut collected,
+                insert_mixin,
+                &extend_mixin_ctx,
+                nest,
+            )?;
+        } else {
+            insert_copied(
+                &mut the_struct_mixin_ctx,
+                &mut collected,
+                insert_mixin,
+                mixin,
+                &extend_mixin_ctx,
+                data,
+            )?;
+        }
+    }
+
+    //祖先的顺序: 两个父类型对共同祖先的先后顺序不一致时没法线性化
+    let parents: Vec<String> = insert_args
+        .mixins
+        .iter()
+        .map(|m| m.name.to_string())
+        .collect();
+    let linearization = c3_linearize(&parents, data).map_err(|heads| {
+        syn::Error::new_spanned(
+            &the_struct_mixin_ctx.name,
+            format!(
+                "can't order the ancestors of `{}`: {} are inherited in conflicting orders; insert them in a consistent order",
+                the_struct_name,
+                heads.iter().map(|h| format!("`{}`", h)).collect::<Vec<_>>().join(", ")
+            ),
+        )
+    })?;
+
+    let field_origins = merge_fields(
+        &mut the_struct_mixin_ctx,
+        std::mem::take(&mut collected.fields),
+        data,
+    )?;
+    merge_variants(
+        &mut the_struct_mixin_ctx,
+        std::mem::take(&mut collected.variants),
+        &insert_args,
+    )?;
+
+    let overwrites = Overwrites::of(&the_struct_mixin_ctx);
+    chain_ctors(&mut the_struct_mixin_ctx, &mut collected, &overwrites, data)?;
+    gen_conversions(&mut the_struct_mixin_ctx, &mut collected)?;
+
+    //enum继承过来的 match self 需要给新加的variant补上分支
+    if the_struct_mixin_ctx.mixin_type == MixinType::Enum {
+        complete_enum_matches(
+            &mut the_struct_mixin_ctx,
+            &collected.enum_fn_origins,
+            &collected.enum_trait_origins,
+        )?;
+    }
+
+    apply_overwrites(&mut the_struct_mixin_ctx, &overwrites);
+    check_origin_conflicts(
+        &the_struct_mixin_ctx,
+        &collected.origin_conflicts,
+        &overwrites,
+    )?;
+    check_required(&the_struct_mixin_ctx, &collected.required_fns)?;
+
+    //添加get_set方法
+    let declaration = the_struct_mixin_ctx.declaration.as_ref().unwrap();
+    //只有类型是Struct时，才需要生产 get_set方法。
+    if let Some(get_set_impls) = gen_get_set_impls(declaration) {
+        the_struct_mixin_ctx.add_extension(&get_set_impls);
+    }
+
+    //记录继承关系，后面再insert这个类型的时候用
+    let (fn_origins, trait_origins) =
+        inherited_origins(&the_struct_mixin_ctx, &collected, &overwrites);
+    the_struct_mixin_ctx.ancestry = Ancestry {
+        parents,
+        linearization,
+        fn_origins,
+        trait_origins,
+        field_origins,
+    };
+
+    let mut stream: TokenStream = the_struct_mixin_ctx.to_token_stream();
+    stream.extend(collected.extra_stream);
+    dump_item(&the_struct_mixin_ctx, &stream, insert_args.dump);
+    let the_struct_mixin = Mixin::from(&the_struct_mixin_ctx);
+
+    //the_struct_mixin_ctx.dbg_print();
+    //dbg!(stream.to_string());
+    //最后把the_struct_mixin放到全局变量， 这里实际会替换原来已经添加了overwrite的mixin。然后overwrite的信息已经没有用了。
+    if replayed.is_none() {
+        registry.mixins.insert(the_struct_name, the_struct_mixin);
+    }
+    Ok(stream)
+}
+
+//embed模式: mixin作为一个字段，方法和trait转发给这个字段
+fn insert_embed<'a>(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    collected: &mut Collected<'a>,
+    insert_mixin: &'a InsertMixin,
+    extend_mixin_ctx: &MixinCtx,
+    field: &Ident,
+) -> Result<(), Error> {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    let mixin_name = insert_mixin.name.to_string();
+    let target_is_struct = the_struct_mixin_ctx.mixin_type == MixinType::Struct;
+    let declaration = extend_mixin_ctx.declaration.as_ref().unwrap();
+    if !target_is_struct
+        || extend_mixin_ctx.mixin_type != MixinType::Struct
+        || !declaration.generics.params.is_empty()
+    {
+        return Err(syn::Error::new_spanned(
+            field,
+            "embed only inserts a non-generic struct into a struct",
+        )
+        .into());
+    }
+    if insert_mixin.check_layout {
+        return Err(syn::Error::new_spanned(
+            field,
+            "check_layout and prefix only apply to copied fields",
+        )
+        .into());
+    }
+    let mixin_ident = &insert_mixin.name;
+    //目标类型没有derive serde的时候不能加 #[serde(..)]
+    let flatten = (insert_mixin.serde == SerdeMode::Flatten
+        && derives_serde(&the_struct_mixin_ctx.declaration.as_ref().unwrap().attrs))
+    .then(|| quote! { #[serde(flatten)] });
+    let embed_fields: Punctuated<Field, Comma> =
+        Punctuated::from_iter([syn::parse::Parser::parse2(
+            Field::parse_named,
+            quote! { #flatten #field: #mixin_ident },
+        )?]);
+    collected.fields.push((
+        embed_fields.clone(),
+        insert_mixin.position.clone(),
+        &insert_mixin.name,
+    ));
+    let target = the_struct_mixin_ctx.declaration.clone().unwrap();
+    let via = DelegateVia::Field(field);
+    let mut rewriter = SelfTypeRewriter::new(&mixin_name);
+    for block in extend_mixin_ctx.extensions.iter() {
+        let mut block = block.clone();
+        let mut items = Vec::new();
+        for item in std::mem::take(&mut block.items) {
+            let ImplItem::Fn(impl_fn) = item else {
+                items.push(item);
+                continue;
+            };
+            //-> Person 也算构造函数
+            let mut probe = impl_fn.clone();
+            rewriter.visit_signature_mut(&mut probe.sig);
+            if is_ctor(&probe) {
+                let mut header = block.clone();
+                let key = block_fn_key(&header, &probe);
+                header.items = vec![ImplItem::Fn(probe)];
+                collected.ctors.push(MixedCtor {
+                    parent: insert_mixin.clone(),
+                    parent_fields: embed_fields.clone(),
+                    ctor: CtorKind::Fn(Box::new(header)),
+                    key,
+                    origin: mixin_name.clone(),
+                });
+                continue;
+            }
+            let fn_name = &impl_fn.sig.ident;
+            match gen_delegate(&impl_fn, quote! { #mixin_ident::#fn_name }, &via) {
+                Some(delegate) => items.push(ImplItem::Fn(delegate)),
+                //目标类型自己overwrite了的不用转发
+                None if overwrites_fn(the_struct_mixin_ctx, fn_name) => {}
+                None => {
+                    return Err(unforwardable(insert_mixin, None, fn_name, &the_struct_name).into())
+                }
+            }
+        }
+        block.items = items;
+        if block.items.is_empty() {
+            continue;
+        }
+        retarget_impl(&mut block, &target)?;
+        for key in impl_fn_keys(&block) {
+            collected.fn_origins.insert(key, mixin_name.clone());
+        }
+        the_struct_mixin_ctx.add_extension(&block);
+    }
+    for (trait_name, trait_impl) in extend_mixin_ctx.impl_traits.iter() {
+        if is_default_impl(trait_impl) {
+            collected.ctors.push(MixedCtor {
+                parent: insert_mixin.clone(),
+                parent_fields: embed_fields.clone(),
+                ctor: CtorKind::Default(trait_name.clone()),
+                key: trait_name.clone(),
+                origin: mixin_name.clone(),
+            });
+            continue;
+        }
+        //目标类型自己overwrite了的trait不用转发
+        if overwrites_trait(the_struct_mixin_ctx, trait_name) {
+            continue;
+        }
+        let trait_impl = forward_trait_impl(trait_impl, trait_name, insert_mixin, &via, &target)?;
+        collected
+            .trait_origins
+            .insert(trait_name.clone(), mixin_name.clone());
+        the_struct_mixin_ctx
+            .impl_traits
+            .insert(trait_name.clone(), trait_impl);
+    }
+    for embed_impl in gen_embed_impls(&target, insert_mixin, field) {
+        the_struct_mixin_ctx
+            .impl_traits
+            .insert(trait_key(&embed_impl), embed_impl);
+    }
+    Ok(())
+}
+
+//nest模式: 整个mixin作为一个variant，方法转发给包起来的值
+fn insert_nest(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    collected: &mut Collected,
+    insert_mixin: &InsertMixin,
+    extend_mixin_ctx: &MixinCtx,
+    nest: &Ident,
+) -> Result<(), Error> {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    let mixin_name = insert_mixin.name.to_string();
+    let target_is_struct = the_struct_mixin_ctx.mixin_type == MixinType::Struct;
+    let declaration = extend_mixin_ctx.declaration.as_ref().unwrap();
+    if target_is_struct
+        || extend_mixin_ctx.mixin_type != MixinType::Enum
+        || !declaration.generics.params.is_empty()
+    {
+        return Err(syn::Error::new_spanned(
+            nest,
+            "nest only inserts a non-generic enum into an enum",
+        )
+        .into());
+    }
+    if let Some((from, _)) = insert_mixin.rename.first() {
+        return Err(syn::Error::new_spanned(from, "rename can't be combined with nest").into());
+    }
+    let mixin_ident = &insert_mixin.name;
+    collected
+        .variants
+        .push((parse_quote!(#nest(#mixin_ident)), mixin_ident.clone()));
+    collected
+        .nest_mixins
+        .push((mixin_ident.clone(), nest.clone()));
+    let origin = EnumFnOrigin {
+        mixin: mixin_ident.clone(),
+        variants: HashSet::from([nest.to_string()]),
+    };
+    let target = the_struct_mixin_ctx.declaration.clone().unwrap();
+    for block in extend_mixin_ctx.extensions.iter() {
+        let mut block = block.clone();
+        let mut items = Vec::new();
+        for item in std::mem::take(&mut block.items) {
+            match item {
+                ImplItem::Fn(impl_fn) => {
+                    let fn_name = &impl_fn.sig.ident;
+                    match gen_delegate(
+                        &impl_fn,
+                        quote! { #mixin_ident::#fn_name },
+                        &DelegateVia::Variant(nest),
+                    ) {
+                        Some(delegate) => items.push(ImplItem::Fn(delegate)),
+                        //目标类型自己overwrite了的不用转发
+                        None if overwrites_fn(the_struct_mixin_ctx, fn_name) => {}
+                        None => {
+                            return Err(unforwardable(
+                                insert_mixin,
+                                None,
+                                fn_name,
+                                &the_struct_name,
+                            )
+                            .into())
+                        }
+                    }
+                }
+                ImplItem::Const(_) => items.push(item),
+                _ => {}
+            }
+        }
+        block.items = items;
+        if block.items.is_empty() {
+            continue;
+        }
+        retarget_impl(&mut block, &target)?;
+        for key in impl_fn_keys(&block) {
+            collected.fn_origins.insert(key.clone(), mixin_name.clone());
+            collected.enum_fn_origins.insert(key, origin.clone());
+        }
+        the_struct_mixin_ctx.add_extension(&block);
+    }
+    for (trait_name, trait_impl) in extend_mixin_ctx.impl_traits.iter() {
+        //目标类型自己overwrite了的trait不用转发
+        if overwrites_trait(the_struct_mixin_ctx, trait_name) {
+            continue;
+        }
+        let trait_impl = forward_trait_impl(
+            trait_impl,
+            trait_name,
+            insert_mixin,
+            &DelegateVia::Variant(nest),
+            &target,
+        )?;
+        collected
+            .trait_origins
+            .insert(trait_name.clone(), mixin_name.clone());
+        collected
+            .enum_trait_origins
+            .insert(trait_name.clone(), origin.clone());
+        the_struct_mixin_ctx
+            .impl_traits
+            .insert(trait_name.clone(), trait_impl);
+    }
+    Ok(())
+}
+
+//复制模式: mixin的字段、variant、fn和trait都复制到目标类型上
+fn insert_copied<'a>(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    collected: &mut Collected<'a>,
+    insert_mixin: &'a InsertMixin,
+    mixin: &Mixin,
+    extend_mixin_ctx: &MixinCtx,
+    data: &HashMap<String, Mixin>,
+) -> Result<(), Error> {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    let mixin_name = insert_mixin.name.to_string();
+    let target_is_struct = the_struct_mixin_ctx.mixin_type == MixinType::Struct;
+    let mixin_decl = extend_mixin_ctx.declaration.as_ref().unwrap();
+    let is_abstract = extend_mixin_ctx.options.is_abstract;
+    //embed和nest时mixin类型自己实现了required的方法，只有展开的时候需要目标类型实现
+    for required_fn in extend_mixin_ctx.required.iter() {
+        collected
+            .required_fns
+            .push((insert_mixin.name.clone(), required_fn.clone()));
+    }
+    //struct的字段只能展开到struct里，enum的variant只能加到enum里
+    match (&mixin_decl.data, target_is_struct) {
+        (Data::Struct(_), false) => {
+            return Err(syn::Error::new_spanned(
+                &insert_mixin.name,
+                format!(
+                    "`{}` is a struct, its fields can't be inserted into the enum `{}`; \
+                     use `#[insert_fields({})]` to add them to every variant",
+                    mixin_name, the_struct_name, mixin_name
+                ),
+            )
+            .into());
+        }
+        (Data::Enum(_), true) => {
+            return Err(syn::Error::new_spanned(
+                &insert_mixin.name,
+                format!(
+                    "`{}` is an enum, its variants can't be inserted into the struct `{}`",
+                    mixin_name, the_struct_name
+                ),
+            )
+            .into());
+        }
+        _ => {}
+    }
+    //泛型的mixin，字段、variant里用到的泛型参数目标类型也得同名声明，impl和转换才写得出来
+    let target_generics = &the_struct_mixin_ctx.declaration.as_ref().unwrap().generics;
+    let missing: Vec<String> = mixin_decl
+        .generics
+        .params
+        .iter()
+        .map(generic_param_name)
+        .filter(|name| {
+            !target_generics
+                .params
+                .iter()
+                .any(|p| generic_param_name(p) == *name)
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &insert_mixin.name,
+            format!(
+                "`{}` is generic over {}; declare the same parameters on `{}`",
+                mixin_name,
+                missing
+                    .iter()
+                    .map(|m| format!("`{}`", m))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                the_struct_name
+            ),
+        )
+        .into());
+    }
+    //将mixin的field 汇总
+    let mut parent_fields = None;
+    if let Data::Struct(st) = mixin_decl.clone().data {
+        if let Fields::Named(named) = st.fields {
+            parent_fields = Some(named.named.clone());
+            collected.fields.push((
+                named.named,
+                insert_mixin.position.clone(),
+                &insert_mixin.name,
+            )); //先把mixin的field push到mixed_fields, 后面将这些field输出到the_struct的field
+            if insert_mixin.check_layout {
+                collected
+                    .layout_checks
+                    .push((insert_mixin, mixin_decl.clone()));
+            }
+        }
+    } else if let Data::Enum(en) = mixin_decl.clone().data {
+        //rename里的variant必须是这个mixin里有的
+        for (from, _) in insert_mixin.rename.iter() {
+            if !en.variants.iter().any(|v| v.ident == *from) {
+                return Err(syn::Error::new_spanned(
+                    from,
+                    format!("`{}` has no variant named `{}`", mixin_name, from),
+                )
+                .into());
+            }
+        }
+        let renamed = |ident: &Ident| {
+            insert_mixin
+                .rename
+                .iter()
+                .find(|(from, _)| from == ident)
+                .map_or_else(|| ident.clone(), |(_, to)| to.clone())
+        };
+        collected.enum_mixins.push(EnumMixin {
+            name: insert_mixin.name.clone(),
+            generics: mixin_decl.generics.clone(),
+            variants: en
+                .variants
+                .iter()
+                .map(|v| (v.clone(), renamed(&v.ident)))
+                .collect(),
+            is_abstract,
+        });
+        for mut variant in en.variants {
+            variant.ident = renamed(&variant.ident);
+            collected
+                .variants
+                .push((variant, insert_mixin.name.clone()));
+        }
+    } else if let Some((from, _)) = insert_mixin.rename.first() {
+        return Err(syn::Error::new_spanned(from, "rename only applies to enum mixins").into());
+    }
+    //直接添加the_struct_mixin_ctx中的fn，如果
+    //继承过来的fn里面出现的mixin的名字需要换成Self，否则 Employee::new 返回的还是Person
+    //impl块整体继承过来，块上的属性(#[cfg]、#[allow]、文档注释等)都保留
+    let mut rewriter = SelfTypeRewriter::new(&mixin_name).with_renames(&insert_mixin.rename);
+    for block in extend_mixin_ctx.extensions.iter() {
+        let mut block = block.clone();
+        let mut items = Vec::new();
+        for mut item in std::mem::take(&mut block.items) {
+            rewriter.visit_impl_item_mut(&mut item);
+            //struct的构造函数不知道目标类型多出来的字段，等字段合并完之后再生成
+            if let (ImplItem::Fn(fn_impl), Some(parent_fields), true) =
+                (&item, parent_fields.as_ref(), target_is_struct)
+            {
+                if is_ctor(fn_impl) {
+                    //abstract的父类型没有构造函数可以调用，不继承
+                    if is_abstract {
+                        continue;
+                    }
+                    let key = block_fn_key(&block, fn_impl);
+                    let origin = mixin
+                        .ancestry
+                        .fn_origins
+                        .get(&key)
+                        .unwrap_or(&mixin_name)
+                        .clone();
+                    let mut header = block.clone();
+                    header.items = vec![item.clone()];
+                    collected.ctors.push(MixedCtor {
+                        parent: insert_mixin.clone(),
+                        parent_fields: parent_fields.clone(),
+                        ctor: CtorKind::Fn(Box::new(header)),
+                        key,
+                        origin,
+                    });
+                    continue;
+                }
+            }
+            //菱形继承: 同一个fn从两条路径过来，按来源决定留哪一份
+            if let ImplItem::Fn(fn_impl) = &item {
+                let key = block_fn_key(&block, fn_impl);
+                let origin = mixin
+                    .ancestry
+                    .fn_origins
+                    .get(&key)
+                    .unwrap_or(&mixin_name)
+                    .clone();
+                if let Some(old) = collected.fn_origins.get(&key) {
+                    match pick_origin(&key, old, &origin, data, |m| &m.ancestry.fn_origins) {
+                        OriginPick::Keep => continue,
+                        OriginPick::Replace => {}
+                        OriginPick::Conflict(common) => {
+                            collected.origin_conflicts.push(OriginConflict {
+                                key: key.clone(),
+                                is_trait: false,
+                                paths: (old.clone(), origin.clone()),
+                                common,
+                            })
+                        }
+                    }
+                }
+                collected.fn_origins.insert(key, origin);
+            }
+            items.push(item);
+        }
+        block.items = items;
+        retarget_impl(
+            &mut block,
+            the_struct_mixin_ctx.declaration.as_ref().unwrap(),
+        )?;
+        if let (Some(origin), false) = (
+            enum_origin(&collected.enum_mixins, insert_mixin),
+            target_is_struct,
+        ) {
+            for key in impl_fn_keys(&block) {
+                collected.enum_fn_origins.insert(key, origin.clone());
+            }
+        }
+        the_struct_mixin_ctx.add_extension(&block);
+    }
+
+    //直接用overwrite的内容覆盖，这里其实有点小问题(也不算问题)：原来没有写impl但是有overwrite的函数也会直接添加进去。
+    //最理想的状态是能够提示overwrite的函数实际之前没有。
+    // dbg!(&mixin_name);
+    // for (fn_name, fn_impl) in extend_mixin_ctx.overwrite_impls.iter() {
+    //     dbg!(&fn_name);
+    //     the_struct_mixin_ctx
+    //         .extensions
+    //         .insert(fn_name.clone(), fn_impl.clone());
+    // }
+
+    //直接添加the_struct_mixin_ctx的trait，
+    for (trait_name, trait_impl) in extend_mixin_ctx.impl_traits.iter() {
+        if let (Some(parent_fields), true, true) = (
+            parent_fields.as_ref(),
+            target_is_struct,
+            is_default_impl(trait_impl),
+        ) {
+            if is_abstract {
+                continue;
+            }
+            collected.ctors.push(MixedCtor {
+                parent: insert_mixin.clone(),
+                parent_fields: parent_fields.clone(),
+                ctor: CtorKind::Default(trait_name.clone()),
+                key: trait_name.clone(),
+                origin: mixin
+                    .ancestry
+                    .trait_origins
+                    .get(trait_name)
+                    .unwrap_or(&mixin_name)
+                    .clone(),
+            });
+            continue;
+        }
+        let origin = mixin
+            .ancestry
+            .trait_origins
+            .get(trait_name)
+            .unwrap_or(&mixin_name)
+            .clone();
+        if let Some(old) = collected.trait_origins.get(trait_name) {
+            match pick_origin(trait_name, old, &origin, data, |m| {
+                &m.ancestry.trait_origins
+            }) {
+                OriginPick::Keep => continue,
+                OriginPick::Replace => {}
+                OriginPick::Conflict(common) => collected.origin_conflicts.push(OriginConflict {
+                    key: trait_name.clone(),
+                    is_trait: true,
+                    paths: (old.clone(), origin.clone()),
+                    common,
+                }),
+            }
+        }
+        collected.trait_origins.insert(trait_name.clone(), origin);
+        //因为这里是ItemImpl, 需要其中的self_ty,再插入到the_struct_mixin_ctx
+        let mut trait_impl = trait_impl.clone();
+        for item in trait_impl.items.iter_mut() {
+            rewriter.visit_impl_item_mut(item);
+        }
+
+        //这里的self_ty一定是Struct的Type，也就是for后面的值，我们需要将其替换成目标struct
+        retarget_impl(
+            &mut trait_impl,
+            the_struct_mixin_ctx.declaration.as_ref().unwrap(),
+        )?;
+
+        if let (Some(origin), false) = (
+            enum_origin(&collected.enum_mixins, insert_mixin),
+            target_is_struct,
+        ) {
+            collected
+                .enum_trait_origins
+                .insert(trait_name.clone(), origin);
+        }
+        the_struct_mixin_ctx
+            .impl_traits
+            .insert(trait_name.clone(), trait_impl);
+    }
+    // //然后再用overwrite的trait去覆盖
+    // for (trait_name, trait_impl) in the_struct_mixin_ctx.over_traits.iter() {
+    //     the_struct_mixin_ctx
+    //         .impl_traits
+    //         .insert(trait_name.clone(), trait_impl.clone());
+    // }
+    Ok(())
+}
+
+//mixin的字段按position合并到目标struct里，返回每个字段最早是哪个类型声明的
+fn merge_fields(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    mixed_fields: Vec<(Punctuated<Field, Comma>, FieldPosition, &Ident)>,
+    data: &HashMap<String, Mixin>,
+) -> Result<HashMap<String, String>, Error> {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    //目标类型没有derive Serialize/Deserialize时，mixin字段上的#[serde]属性去掉
+    let keep_serde = derives_serde(&the_struct_mixin_ctx.declaration.as_ref().unwrap().attrs);
+    let mut field_origins: HashMap<String, String> = HashMap::new();
+    let Data::Struct(ref mut st) = the_struct_mixin_ctx.declaration.as_mut().unwrap().data else {
+        return Ok(field_origins);
+    };
+    if let Fields::Named(ref mut named) = st.fields {
+        let mut merger = FieldMerger::new(&the_struct_mixin_ctx.name, &named.named); //先记录自己的field
+        for ident in named.named.iter().filter_map(|f| f.ident.as_ref()) {
+            field_origins.insert(ident.to_string(), the_struct_name.clone());
+        }
+
+        //遍历mixin的field，并添加到new_fields中，跳过自己已经有的字段
+        let mut first_index = 0; //position = first 的字段按insert的顺序放在最前面
+        for (fields, position, from) in mixed_fields {
+            let mut new_fields: Punctuated<Field, Comma> = Punctuated::new();
+            for field in fields.iter() {
+                //同时把添加的filed记录到merger，避免多个mixin中有相同的filed导致最后有问题。
+                if let Some(mut field) = merger.merge(field, from)? {
+                    //mixin自己也是继承来的字段，记最早声明它的类型
+                    if let Some(ident) = field.ident.as_ref() {
+                        let name = ident.to_string();
+                        let origin = data
+                            .get(&from.to_string())
+                            .and_then(|m| m.ancestry.field_origins.get(&name))
+                            .cloned()
+                            .unwrap_or_else(|| from.to_string());
+                        field_origins.entry(name).or_insert(origin);
+                    }
+                    if !keep_serde {
+                        field.attrs.retain(|a| !is_serde_attr(a));
+                    }
+                    new_fields.push(field);
+                }
+            }
+            //把new fields按position添加到最终的输出。
+            let index = match &position {
+                FieldPosition::Last => named.named.len(),
+                FieldPosition::First => first_index,
+                FieldPosition::After(anchor) => named
+                    .named
+                    .iter()
+                    .position(|f| f.ident.as_ref() == Some(anchor))
+                    .map(|i| i + 1)
+                    .ok_or_else(|| {
+                        syn::Error::new_spanned(
+                            anchor,
+                            format!(
+                                "`{}` has no field named `{}`",
+                                the_struct_mixin_ctx.name, anchor
+                            ),
+                        )
+                    })?,
+            };
+            let count = new_fields.len();
+            for (i, field) in new_fields.into_iter().enumerate() {
+                named.named.insert(index + i, field);
+            }
+            if position == FieldPosition::First || index < first_index {
+                first_index += count;
+            }
+        }
+    } else if let Some((_, _, from)) = mixed_fields
+        .iter()
+        .find(|(fields, _, _)| !fields.is_empty())
+    {
+        return Err(syn::Error::new_spanned(
+            from,
+            format!(
+                "`{}` needs named fields `{{ .. }}` to receive the fields of `{}`",
+                the_struct_mixin_ctx.name, from
+            ),
+        )
+        .into());
+    }
+    Ok(field_origins)
+}
+
+//同名的variant只保留一个，但是形状(unit/tuple/struct、字段类型、discriminant)必须一样，否则报错
+fn merge_variants(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    mixed_variants: Vec<(Variant, Ident)>,
+    insert_args: &InsertArgs,
+) -> Result<(), Error> {
+    let Data::Enum(ref mut en) = the_struct_mixin_ctx.declaration.as_mut().unwrap().data else {
+        return Ok(());
+    };
+    let mut the_enum_varients: HashMap<String, (Variant, Option<Ident>)> = HashMap::new();
+    for variant in en.variants.iter() {
+        the_enum_varients.insert(variant.ident.to_string(), (variant.clone(), None));
+    }
+    let mut errors: Option<syn::Error> = None;
+    let mut new_variants: Punctuated<Variant, Comma> = Punctuated::new();
+    for (variant, from) in mixed_variants {
+        let Some((existing, existing_from)) = the_enum_varients.get(&variant.ident.to_string())
+        else {
+            the_enum_varients.insert(variant.ident.to_string(), (variant.clone(), Some(from)));
+            new_variants.push(variant);
+            continue;
+        };
+        if same_variant_shape(existing, &variant) {
+            continue;
+        }
+        //自己声明的variant有准确的位置，mixin里的variant是从字符串解析的，只能指向insert的参数
+        let existing_desc = match existing_from {
+            Some(existing_from) => {
+                format!("`{}` from `{}`", variant_shape(existing), existing_from)
+            }
+            None => format!("`{}`", variant_shape(existing)),
+        };
+        //rename过的variant，新名字和已有的variant撞了，要换的是rename的新名字
+        let renamed_from = insert_args
+            .mixins
+            .iter()
+            .find(|m| m.name == from)
+            .and_then(|m| m.rename.iter().find(|(_, to)| *to == variant.ident));
+        let message = match renamed_from {
+            Some((old, _)) => format!(
+                "`rename({} = {})` on `{}` gives `{}`, a name that collides with {} in `{}`; \
+                 rename it to a variant name `{}` doesn't use",
+                old,
+                variant.ident,
+                from,
+                variant_shape(&variant),
+                existing_desc,
+                the_struct_mixin_ctx.name,
+                the_struct_mixin_ctx.name
+            ),
+            None => format!(
+                "variant `{}` from `{}` is `{}`, which clashes with {} in `{}`; \
+                 use `#[insert({}, rename({} = ...))]` to keep both",
+                variant.ident,
+                from,
+                variant_shape(&variant),
+                existing_desc,
+                the_struct_mixin_ctx.name,
+                from,
+                variant.ident
+            ),
+        };
+        let error = match (renamed_from, existing_from) {
+            (Some((_, to)), _) => syn::Error::new_spanned(to, message),
+            (None, Some(_)) => syn::Error::new_spanned(&from, message),
+            (None, None) => syn::Error::new_spanned(&existing.ident, message),
+        };
+        match errors.as_mut() {
+            Some(errors) => errors.combine(error),
+            None => errors = Some(error),
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors.into());
+    }
+    en.variants.extend(new_variants.into_pairs());
+    Ok(())
+}
+
+//字段合并完了，现在可以用父类型的构造函数生成目标类型的构造函数
+//同名的构造函数(或者Default)从几个父类型过来时和fn一样按来源挑: 同一个祖先的只算一次，留先insert的；
+//更具体的优先；两边来源不相关或者各自overwrite了共同祖先的，目标类型必须自己overwrite
+fn chain_ctors(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    collected: &mut Collected,
+    overwrites: &Overwrites,
+    data: &HashMap<String, Mixin>,
+) -> Result<(), Error> {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    let mut chosen: Vec<&MixedCtor> = Vec::new();
+    for mixed_ctor in collected.ctors.iter() {
+        let Some(index) = chosen.iter().position(|c| c.key == mixed_ctor.key) else {
+            chosen.push(mixed_ctor);
+            continue;
+        };
+        let old = chosen[index];
+        let is_trait = matches!(mixed_ctor.ctor, CtorKind::Default(_));
+        let overwritten = if is_trait {
+            overwrites.trait_names.contains(key_base(&mixed_ctor.key))
+        } else {
+            overwrites.fn_names.contains(key_base(&mixed_ctor.key))
+        };
+        if overwritten || old.parent.name == mixed_ctor.parent.name {
+            continue;
+        }
+        let pick = if is_trait {
+            pick_origin(
+                &mixed_ctor.key,
+                &old.origin,
+                &mixed_ctor.origin,
+                data,
+                |m| &m.ancestry.trait_origins,
+            )
+        } else {
+            pick_origin(
+                &mixed_ctor.key,
+                &old.origin,
+                &mixed_ctor.origin,
+                data,
+                |m| &m.ancestry.fn_origins,
+            )
+        };
+        match pick {
+            OriginPick::Keep => continue,
+            OriginPick::Replace if is_ancestor(&old.origin, &mixed_ctor.origin, data) => {
+                chosen[index] = mixed_ctor;
+                continue;
+            }
+            _ => {}
+        }
+        let how = if is_trait {
+            format!("#[overwrite] impl Default for {}", the_struct_name)
+        } else {
+            format!("#[overwrite] impl {}", the_struct_name)
+        };
+        return Err(syn::Error::new_spanned(
+            &mixed_ctor.parent.name,
+            format!(
+                "`{}` and `{}` give different constructors `{}`; overwrite it in `{}` with `{} {{ .. }}` placed before the insert",
+                old.parent.name,
+                mixed_ctor.parent.name,
+                key_base(&mixed_ctor.key),
+                the_struct_name,
+                how
+            ),
+        )
+        .into());
+    }
+    for mixed_ctor in chosen.iter() {
+        let origins = match mixed_ctor.ctor {
+            CtorKind::Fn(_) => &mut collected.fn_origins,
+            CtorKind::Default(_) => &mut collected.trait_origins,
+        };
+        origins.insert(mixed_ctor.key.clone(), mixed_ctor.origin.clone());
+    }
+    for mixed_ctor in chosen {
+        let declaration = the_struct_mixin_ctx.declaration.as_ref().unwrap();
+        match mixed_ctor.ctor {
+            CtorKind::Fn(ref ctor_block) => {
+                let mut block = ctor_block.as_ref().clone();
+                if let Some(ImplItem::Fn(ctor)) = block.items.first_mut() {
+                    *ctor = gen_chained_ctor(mixed_ctor, ctor, declaration);
+                }
+                retarget_impl(&mut block, declaration)?;
+                the_struct_mixin_ctx.add_extension(&block);
+            }
+            CtorKind::Default(ref trait_name) => {
+                let chained = gen_chained_default(mixed_ctor, declaration)?;
+                the_struct_mixin_ctx
+                    .impl_traits
+                    .insert(trait_name.clone(), chained);
+            }
+        }
+    }
+    Ok(())
+}
+
+//不跟着继承下去的代码: repr(C)的布局检查，enum之间的转换
+fn gen_conversions(
+    the_struct_mixin_ctx: &mut MixinCtx,
+    collected: &mut Collected,
+) -> Result<(), Error> {
+    //repr(C)的前缀布局检查，编译期的断言直接输出。
+    //as_person也直接输出，不跟着继承下去，继承的类型布局没有检查过
+    for (insert_mixin, mixin_decl) in collected.layout_checks.iter() {
+        let declaration = the_struct_mixin_ctx.declaration.as_ref().unwrap();
+        collected.extra_stream.extend(gen_layout_assertions(
+            declaration,
+            insert_mixin,
+            mixin_decl,
+        )?);
+        if insert_mixin.prefix {
+            collected
+                .extra_stream
+                .extend(gen_prefix_casts(declaration, &insert_mixin.name));
+        }
+    }
+
+    //enum之间的转换: From<DeviceMessage> for APMSG、TryFrom<APMSG> for DeviceMessage，
+    //以及 is_device_message/as_device_message，作用和struct的get_*/set_*一样
+    if the_struct_mixin_ctx.mixin_type == MixinType::Enum {
+        for enum_mixin in collected.enum_mixins.iter().filter(|m| !m.is_abstract) {
+            let declaration = the_struct_mixin_ctx.declaration.as_ref().unwrap();
+            let (from_impl, helpers, try_from_impl) = gen_enum_conversions(declaration, enum_mixin);
+            the_struct_mixin_ctx
+                .impl_traits
+                .insert(trait_key(&from_impl), from_impl);
+            the_struct_mixin_ctx.add_extension(&helpers);
+            //TryFrom是实现在mixin上的，不需要跟着目标类型继承下去，直接输出
+            collected.extra_stream.extend(try_from_impl);
+        }
+        for (mixin_ident, nest) in collected.nest_mixins.iter() {
+            let declaration = the_struct_mixin_ctx.declaration.as_ref().unwrap();
+            let (from_impl, helpers, try_from_impl) =
+                gen_nest_conversions(declaration, mixin_ident, nest);
+            the_struct_mixin_ctx
+                .impl_traits
+                .insert(trait_key(&from_impl), from_impl);
+            the_struct_mixin_ctx.add_extension(&helpers);
+            collected.extra_stream.extend(try_from_impl);
+        }
+    }
+    Ok(())
+}
+
+//overwrite 是在将自己mixin中的overwrite，在insert其他minxin之后进行覆盖。之前的代码逻辑是错误的。
+//overwrite会覆盖掉所有cfg下的同名fn/trait
+fn apply_overwrites(the_struct_mixin_ctx: &mut MixinCtx, overwrites: &Overwrites) {
+    remove_fns(&mut the_struct_mixin_ctx.extensions, |k| {
+        overwrites.fn_names.contains(key_base(k))
+    });
+    the_struct_mixin_ctx
+        .impl_traits
+        .retain(|k, _| !overwrites.trait_names.contains(key_base(k)));
+    for block in overwrites.impls.iter() {
+        the_struct_mixin_ctx.extensions.push(block.clone());
+    }
+    for (trait_name, trait_impl) in the_struct_mixin_ctx.over_traits.iter() {
+        the_struct_mixin_ctx
+            .impl_traits
+            .insert(trait_name.clone(), trait_impl.clone());
+    }
+}
+
+//两条继承路径各自overwrite了共同祖先的同一个fn/trait，目标类型必须自己overwrite来决定用哪个
+fn check_origin_conflicts(
+    the_struct_mixin_ctx: &MixinCtx,
+    origin_conflicts: &[OriginConflict],
+    overwrites: &Overwrites,
+) -> Result<(), Error> {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    let mut errors: Option<syn::Error> = None;
+    for conflict in origin_conflicts.iter() {
+        let resolved = if conflict.is_trait {
+            overwrites.trait_names.contains(key_base(&conflict.key))
+        } else {
+            overwrites.fn_names.contains(key_base(&conflict.key))
+        };
+        if resolved {
+            continue;
+        }
+        let (what, how) = if conflict.is_trait {
+            (
+                format!("trait `{}`", key_base(&conflict.key)),
+                format!(
+                    "#[overwrite] impl {} for {}",
+                    key_base(&conflict.key),
+                    the_struct_name
+                ),
+            )
+        } else {
+            (
+                format!("`{}`", key_base(&conflict.key)),
+                format!("#[overwrite] impl {}", the_struct_name),
+            )
+        };
+        let error = syn::Error::new_spanned(
+            &the_struct_mixin_ctx.declaration.as_ref().unwrap().ident,
+            format!(
+                "{} is overwritten by both `{}` and `{}`, which share `{}`; overwrite it in `{}` with `{} {{ .. }}` placed before the insert",
+                what, conflict.paths.0, conflict.paths.1, conflict.common, the_struct_name, how
+            ),
+        );
+        match errors.as_mut() {
+            Some(errors) => errors.combine(error),
+            None => errors = Some(error),
+        }
+    }
+    if let Some(errors) = errors {
+        return Err(errors.into());
+    }
+    Ok(())
+}
+
+//mixin里 #[required] 的方法，目标类型必须在overwrite里实现
+fn check_required(
+    the_struct_mixin_ctx: &MixinCtx,
+    required_fns: &[(Ident, ForeignItemFn)],
+) -> Result<(), Error> {
+    let implemented: HashSet<String> = the_struct_mixin_ctx
+        .extensions
+        .iter()
+        .flat_map(impl_fn_keys)
+        .map(|k| key_base(&k).to_string())
+        .collect();
+    let missing: Vec<String> = required_fns
+        .iter()
+        .filter(|(_, required_fn)| !implemented.contains(&required_fn.sig.ident.to_string()))
+        .map(|(mixin, required_fn)| {
+            format!(
+                "`{}` required by `{}`",
+                display_tokens(required_fn.sig.to_token_stream()),
+                mixin
+            )
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &the_struct_mixin_ctx.declaration.as_ref().unwrap().ident,
+            format!(
+                "`{}` is missing {}; implement them in `#[overwrite] impl {} {{ .. }}` placed before the insert",
+                the_struct_mixin_ctx.name,
+                missing.join(", "),
+                the_struct_mixin_ctx.name
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+//继承过来的fn/trait各自是哪个类型定义的，目标类型自己overwrite了的算目标类型的
+fn inherited_origins(
+    the_struct_mixin_ctx: &MixinCtx,
+    collected: &Collected,
+    overwrites: &Overwrites,
+) -> (HashMap<String, String>, HashMap<String, String>) {
+    let the_struct_name = the_struct_mixin_ctx.name.to_string();
+    let fn_origins = the_struct_mixin_ctx
+        .extensions
+        .iter()
+        .flat_map(impl_fn_keys)
+        .map(|key| {
+            let origin = match collected.fn_origins.get(&key) {
+                Some(origin) if !overwrites.fn_names.contains(key_base(&key)) => origin.clone(),
+                _ => the_struct_name.clone(),
+            };
+            (key, origin)
+        })
+        .collect();
+    let trait_origins = the_struct_mixin_ctx
+        .impl_traits
+        .keys()
+        .map(|key| {
+            let origin = match collected.trait_origins.get(key) {
+                Some(origin) if !overwrites.trait_names.contains(key_base(key)) => origin.clone(),
+                _ => the_struct_name.clone(),
+            };
+            (key.clone(), origin)
+        })
+        .collect();
+    (fn_origins, trait_origins)
+}
+
+//mixin的字段合并到目标类型上。同名字段以先出现的为准，但是如果先出现的字段带了#[cfg]，
+//后面的同名字段在它不生效的时候补上: #[cfg(all(自己的cfg, not(any(前面的cfg...))))]
+pub(crate) struct FieldMerger {
+    present: HashMap<String, Vec<PresentField>>,
+    target: String,
+}
+
+struct PresentField {
+    cfg: Option<TokenStream>,
+    ty: Type,             //normalize_type过的，用来比较
+    from: Option<String>, //None是目标类型自己的字段
+}
+
+impl FieldMerger {
+    pub(crate) fn new(target: &Ident, fields: &Punctuated<Field, Comma>) -> Self {
+        let mut merger = FieldMerger {
+            present: HashMap::new(),
+            target: target.to_string(),
+        };
+        for field in fields.iter() {
+            if let Some(idt) = field.ident.as_ref() {
+                merger
+                    .present
+                    .entry(idt.to_string())
+                    .or_default()
+                    .push(PresentField {
+                        cfg: cfg_predicate(&field.attrs),
+                        ty: normalize_type(&field.ty),
+                        from: None,
+                    });
+            }
+        }
+        merger
+    }
+
+    //返回需要添加到目标类型上的字段，已经有了就返回None。
+    //同名字段的类型不一样时报错(带cfg的字段不检查，不同cfg下类型本来就可以不一样)
+    pub(crate) fn merge(
+        &mut self,
+        field: &Field,
+        from: &Ident,
+    ) -> Result<Option<Field>, syn::Error> {
+        let Some(name) = field.ident.as_ref() else {
+            return Ok(None);
+        };
+        let own = cfg_predicate(&field.attrs);
+        let ty = normalize_type(&field.ty);
+        let existing = self.present.entry(name.to_string()).or_default();
+        if let Some(conflict) = existing.iter().find(|p| p.cfg.is_none() && p.ty != ty) {
+            let previous = match conflict.from.as_ref() {
+                Some(previous) => format!("from `{}`", previous),
+                None => format!("in `{}`", self.target),
+            };
+            return Err(syn::Error::new_spanned(
+                from,
+                format!(
+                    "field `{}: {}` from `{}` conflicts with `{}: {}` {}",
+                    name,
+                    display_type(&field.ty),
+                    from,
+                    name,
+                    display_type(&conflict.ty),
+                    previous
+                ),
+            ));
+        }
+        if existing.iter().any(|p| p.cfg.is_none()) {
+            return Ok(None);
+        }
+        let mut field = field.clone();
+        if !existing.is_empty() {
+            let others: Vec<&TokenStream> =
+                existing.iter().filter_map(|p| p.cfg.as_ref()).collect();
+            let pred = match own.as_ref() {
+                Some(own_pred) => quote! { all(#own_pred, not(any(#(#others),*))) },
+                None => quote! { not(any(#(#others),*)) },
+            };
+            field.attrs.retain(|a| !is_cfg(a));
+            field.attrs.insert(0, parse_quote!(#[cfg(#pred)]));
+        }
+        existing.push(PresentField {
+            cfg: own,
+            ty,
+            from: Some(from.to_string()),
+        });
+        Ok(Some(field))
+    }
+}
+
+//#[declare(final)] 的mixin不能insert；allow_insert_in 限制了能insert的模块。
+//proc macro不知道自己在哪个模块里展开，所以生成一个编译期的断言去检查 module_path!()
+pub(crate) fn check_insert_allowed(mixin: &MixinCtx, arg: &Ident) -> Result<TokenStream, Error> {
+    if mixin.options.is_final {
+        return Err(syn::Error::new_spanned(
+            arg,
+            format!("`{}` is final and can't be inserted", mixin.name),
+        )
+        .into());
+    }
+    if mixin.options.allow_insert_in.is_empty() {
+        return Ok(TokenStream::new());
+    }
+    //module_path!() 的第一段是crate的名字，crate:: 开头的路径只比较后面的部分
+    let allowed = mixin.options.allow_insert_in.iter().map(|path| {
+        match path.strip_prefix("crate") {
+            Some(rest) if rest.is_empty() || rest.starts_with("::") => {
+                let rest = rest.trim_start_matches("::");
+                quote! { __in_module(__skip_crate(module_path!().as_bytes()), #rest.as_bytes()) }
+            }
+            _ => quote! { __in_module(module_path!().as_bytes(), #path.as_bytes()) },
+        }
+    });
+    let message = format!(
+        "`{}` can only be inserted in {}",
+        mixin.name,
+        mixin.options.allow_insert_in.join(", ")
+    );
+    Ok(quote! {
+        const _: () = {
+            //path是module或者它的子模块
+            const fn __in_module(path: &[u8], module: &[u8]) -> bool {
+                if module.is_empty() {
+                    return true;
+                }
+                if path.len() < module.len() {
+                    return false;
+                }
+                let mut i = 0;
+                while i < module.len() {
+                    if path[i] != module[i] {
+                        return false;
+                    }
+                    i += 1;
+                }
+                path.len() == module.len() || (path.len() > module.len() + 1 && path[module.len()] == b':')
+            }
+            const fn __skip_crate(path: &[u8]) -> &[u8] {
+                let mut i = 0;
+                while i + 1 < path.len() {
+                    if path[i] == b':' && path[i + 1] == b':' {
+                        return path.split_at(i + 2).1;
+                    }
+                    i += 1;
+                }
+                &[]
+            }
+            assert!(#(#allowed)||*, #message);
+        };
+    })
+}
@@ -0,0 +1,146 @@
+//! check_layout/prefix: repr(C)的前缀布局检查和 as_* 转换
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::{Attribute, Data, DataStruct, DeriveInput, Fields};
+
+use crate::args::InsertMixin;
+use crate::util::is_cfg;
+use crate::Error;
+
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let mut found = false;
+        if attr.path().is_ident("repr") {
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= meta.path.is_ident("C");
+                Ok(())
+            });
+        }
+        found
+    })
+}
+
+//#[repr(C)]的时候，mixin的字段在目标类型里的偏移和类型都和mixin一样，目标类型的开头可以当作mixin来看。
+//这里生成编译期的断言，字段顺序不对的时候编译失败
+pub(crate) fn gen_layout_assertions(
+    target: &DeriveInput,
+    insert_mixin: &InsertMixin,
+    mixin: &DeriveInput,
+) -> Result<TokenStream, Error> {
+    let mixin_arg = &insert_mixin.name;
+    let name = &target.ident;
+    let mixin_name = &mixin.ident;
+    if !is_repr_c(&target.attrs) {
+        return Err(syn::Error::new_spanned(
+            name,
+            format!(
+                "checking the layout of `{}` needs #[repr(C)] on `{}`",
+                mixin_name, name
+            ),
+        )
+        .into());
+    }
+    if !is_repr_c(&mixin.attrs) {
+        return Err(syn::Error::new_spanned(
+            mixin_arg,
+            format!(
+                "checking the layout of `{}` needs #[repr(C)] on `{}`",
+                mixin_name, mixin_name
+            ),
+        )
+        .into());
+    }
+    if !target.generics.params.is_empty() || !mixin.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            mixin_arg,
+            "checking the layout doesn't support generic types",
+        )
+        .into());
+    }
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(named),
+        ..
+    }) = &mixin.data
+    else {
+        return Err(syn::Error::new_spanned(
+            mixin_arg,
+            "checking the layout needs a struct with named fields",
+        )
+        .into());
+    };
+    let checks = named.named.iter().filter_map(|f| {
+        let field = f.ident.as_ref()?;
+        let ty = &f.ty;
+        let cfgs: Vec<&Attribute> = f.attrs.iter().filter(|a| is_cfg(a)).collect();
+        let message = format!("field `{}` of `{}` is not at the same offset in `{}`", field, mixin_name, name);
+        Some(quote! {
+            #(#cfgs)*
+            let _: fn(&#name) -> &#ty = |t| &t.#field;
+            #(#cfgs)*
+            assert!(::core::mem::offset_of!(#name, #field) == ::core::mem::offset_of!(#mixin_name, #field), #message);
+        })
+    });
+    //prefix时 &mut Person 可以整个被赋值，Person末尾的padding里不能有目标类型自己的字段
+    let mut own_checks = Vec::new();
+    if let (
+        true,
+        Data::Struct(DataStruct {
+            fields: Fields::Named(target_fields),
+            ..
+        }),
+    ) = (insert_mixin.prefix, &target.data)
+    {
+        let mixin_fields: HashSet<String> = named
+            .named
+            .iter()
+            .filter_map(|f| f.ident.as_ref().map(|i| i.to_string()))
+            .collect();
+        for f in target_fields.named.iter() {
+            let Some(field) = f.ident.as_ref() else {
+                continue;
+            };
+            if mixin_fields.contains(&field.to_string()) {
+                continue;
+            }
+            let cfgs: Vec<&Attribute> = f.attrs.iter().filter(|a| is_cfg(a)).collect();
+            let message = format!("field `{}` of `{}` overlaps `{}`", field, name, mixin_name);
+            own_checks.push(quote! {
+                #(#cfgs)*
+                assert!(::core::mem::offset_of!(#name, #field) >= ::core::mem::size_of::<#mixin_name>(), #message);
+            });
+        }
+    }
+    let size_message = format!("`{}` is larger than `{}`", mixin_name, name);
+    let align_message = format!("`{}` is less aligned than `{}`", name, mixin_name);
+    Ok(quote! {
+        const _: () = {
+            #(#checks)*
+            #(#own_checks)*
+            assert!(::core::mem::size_of::<#mixin_name>() <= ::core::mem::size_of::<#name>(), #size_message);
+            assert!(::core::mem::align_of::<#name>() % ::core::mem::align_of::<#mixin_name>() == 0, #align_message);
+        };
+    })
+}
+
+//prefix: 目标类型的开头就是mixin，可以不复制直接当作mixin的引用
+pub(crate) fn gen_prefix_casts(target: &DeriveInput, mixin: &Ident) -> TokenStream {
+    let name = &target.ident;
+    let snake = mixin.to_string().to_case(Case::Snake);
+    let as_fn = format_ident!("as_{}", snake);
+    let as_mut_fn = format_ident!("as_{}_mut", snake);
+    quote! {
+        impl #name {
+            pub fn #as_fn(&self) -> &#mixin {
+                //SAFETY: 两个类型都是repr(C)，上面的断言保证了字段的类型和偏移一样，大小和对齐也满足
+                unsafe { &*(self as *const Self as *const #mixin) }
+            }
+
+            pub fn #as_mut_fn(&mut self) -> &mut #mixin {
+                //SAFETY: 同上，目标类型自己的字段都在mixin的大小之后，整个赋值也不会覆盖到
+                unsafe { &mut *(self as *mut Self as *mut #mixin) }
+            }
+        }
+    }
+}
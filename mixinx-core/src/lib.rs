@@ -0,0 +1,59 @@
+//! mixinx的mixin引擎，不依赖proc_macro，在 `proc_macro2::TokenStream` 上工作。
+//!
+//! mixinx的属性宏只是把 [`Registry::global`] 包了一层；build.rs、别的宏或者测试里可以直接用 [`Registry`]，
+//! 取出来的 [`MixinCtx`] 是解析好的声明和impl。
+use proc_macro2::{Span, TokenStream};
+use thiserror::Error;
+
+mod args;
+mod ctor;
+mod declare;
+mod delegate;
+mod dump;
+mod enums;
+mod graph;
+mod insert;
+mod layout;
+mod mixin;
+mod origin;
+mod registry;
+mod replay;
+mod rewrite;
+mod suggest;
+mod util;
+
+pub use declare::gen_get_set_impls;
+pub use mixin::MixinCtx;
+pub use registry::{mixin_macro, Registry, MIXIN_MACROS};
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) enum MixinType {
+    Unknown,
+    Enum,
+    Struct,
+}
+
+#[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum Error {
+    #[error("invalid expansion of the mixin")]
+    InvalidExpansion,
+    #[error("syn error: {0}")]
+    SynError(#[from] syn::Error),
+    #[error("lex error: {0}")]
+    LexError(#[from] proc_macro2::LexError),
+    //#[error("Struct should be not declear more one")]
+    //StructDeclearMoreOne,
+}
+
+impl Error {
+    #[allow(clippy::wrong_self_convention)]
+    pub(crate) fn to_compile_error(self) -> TokenStream {
+        if let Error::SynError(e) = self {
+            //syn::Error自己带了span，直接用
+            return e.to_compile_error();
+        }
+        let txt = self.to_string();
+        syn::Error::new(Span::call_site(), txt).to_compile_error()
+    }
+}
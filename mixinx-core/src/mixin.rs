@@ -0,0 +1,354 @@
+//! 登记在registry里的mixin，以及解析出来的 [`MixinCtx`]
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use std::collections::{HashMap, HashSet};
+use syn::parse::Parser;
+use syn::visit_mut::VisitMut;
+use syn::{Attribute, DeriveInput, ForeignItemFn, ImplItem, ImplItemFn, ItemImpl, Path};
+
+use crate::origin::Ancestry;
+use crate::rewrite::MixinAttrStripper;
+use crate::util::cfg_key;
+use crate::{Error, MixinType};
+
+#[derive(Clone, Debug)]
+pub(crate) struct Mixin {
+    pub(crate) name: String,
+    mixin_type: MixinType,
+    pub(crate) declaration: Option<String>, //这里是struct结构体的声明，直接用String类型，相当于是源码。
+    //不用TokenStream或者DeriveInput是因为DeriveInput不能跨线程使用。
+    extensions: Vec<String>, //这里是struct所有impl块的声明，每个impl块一个String，相当于直接保存的源码。
+    overwrite_impls: Vec<String>, //overwrite的impl块的源码
+    impl_traits: HashMap<String, String>, //key 的string是trait name, val的string是源码。
+    over_traits: HashMap<String, String>,
+    pub(crate) options: DeclareOptions,
+    required: Vec<String>, //#[required] fn id(&self) -> u64; 的源码
+    pub(crate) ancestry: Ancestry,
+}
+
+//#[declare(..)] 的参数
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DeclareOptions {
+    //只用来insert，不输出类型本身以及它的impl
+    pub(crate) is_abstract: bool,
+    //不能被insert
+    pub(crate) is_final: bool,
+    //只能在这些模块(以及子模块)里insert，例如 "crate::devices"
+    pub(crate) allow_insert_in: Vec<String>,
+}
+
+impl DeclareOptions {
+    pub(crate) fn parse(args: TokenStream) -> syn::Result<Self> {
+        let mut options = DeclareOptions::default();
+        //和final冲突的选项(abstract、allow_insert_in)，报错的时候指向它
+        let mut conflicting: Option<Path> = None;
+        //abstract是关键字，不能当作Meta解析，用syn::meta::parser
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("abstract") || meta.path.is_ident("allow_insert_in") {
+                conflicting.get_or_insert_with(|| meta.path.clone());
+            }
+            if meta.path.is_ident("abstract") {
+                options.is_abstract = true;
+                Ok(())
+            } else if meta.path.is_ident("final") {
+                options.is_final = true;
+                Ok(())
+            } else if meta.path.is_ident("allow_insert_in") {
+                let path: syn::LitStr = meta.value()?.parse()?;
+                //检查一下是不是合法的模块路径
+                let module = syn::parse_str::<Path>(&path.value()).map_err(|_| {
+                    syn::Error::new_spanned(&path, "expected a module path like \"crate::devices\"")
+                })?;
+                //module_path!()总是从crate的名字开始，相对路径永远匹配不上
+                if module
+                    .segments
+                    .first()
+                    .is_some_and(|s| s.ident == "self" || s.ident == "super")
+                {
+                    return Err(syn::Error::new_spanned(
+                        &path,
+                        "`allow_insert_in` needs a path from the crate root like \"crate::devices\", not a `self::`/`super::` path",
+                    ));
+                }
+                options.allow_insert_in.push(path.value().replace(' ', ""));
+                Ok(())
+            } else {
+                Err(meta.error("unknown declare option"))
+            }
+        })
+        .parse2(args)?;
+        if let (true, Some(conflicting)) = (options.is_final, conflicting) {
+            return Err(syn::Error::new_spanned(
+                conflicting,
+                "a final mixin can't be inserted, `abstract` and `allow_insert_in` make no sense with it",
+            ));
+        }
+        Ok(options)
+    }
+}
+
+/// 解析过的mixin: 类型的声明，expand进来和继承来的impl块、trait impl，以及它的祖先
+pub struct MixinCtx {
+    pub(crate) name: Ident,
+    pub(crate) mixin_type: MixinType,
+    pub(crate) declaration: Option<DeriveInput>,
+    //impl块作为整体保存，块上的属性、unsafe等都原样保留；同名的fn在块之间去重
+    pub(crate) extensions: Vec<ItemImpl>,
+    pub(crate) overwrite_impls: Vec<ItemImpl>,
+    pub(crate) impl_traits: HashMap<String, ItemImpl>, //key的String是trait name
+    pub(crate) over_traits: HashMap<String, ItemImpl>,
+    pub(crate) options: DeclareOptions,
+    //insert的目标类型必须通过overwrite实现的方法
+    pub(crate) required: Vec<ForeignItemFn>,
+    pub(crate) ancestry: Ancestry,
+}
+
+//fn的key需要带上impl块和fn自己的#[cfg]
+pub(crate) fn block_fn_key(block: &ItemImpl, impl_fn: &ImplItemFn) -> String {
+    let attrs: Vec<Attribute> = block
+        .attrs
+        .iter()
+        .chain(impl_fn.attrs.iter())
+        .cloned()
+        .collect();
+    cfg_key(&impl_fn.sig.ident.to_string(), &attrs)
+}
+
+pub(crate) fn impl_fn_keys(block: &ItemImpl) -> Vec<String> {
+    block
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(impl_fn) => Some(block_fn_key(block, impl_fn)),
+            _ => None,
+        })
+        .collect()
+}
+
+//从impl块里删掉key满足条件的fn，删空了的块也一起去掉
+pub(crate) fn remove_fns(blocks: &mut Vec<ItemImpl>, pred: impl Fn(&str) -> bool) {
+    for block in blocks.iter_mut() {
+        let header = ItemImpl {
+            items: Vec::new(),
+            ..block.clone()
+        };
+        block.items.retain(|item| match item {
+            ImplItem::Fn(impl_fn) => !pred(&block_fn_key(&header, impl_fn)),
+            _ => true,
+        });
+    }
+    blocks.retain(|block| !block.items.is_empty());
+}
+
+impl MixinCtx {
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn is_enum(&self) -> bool {
+        self.mixin_type == MixinType::Enum
+    }
+
+    /// 类型的声明，insert的目标是合并之后的
+    pub fn declaration(&self) -> Option<&DeriveInput> {
+        self.declaration.as_ref()
+    }
+
+    /// 不带trait的impl块，包括get/set和继承来的
+    pub fn extensions(&self) -> &[ItemImpl] {
+        &self.extensions
+    }
+
+    /// trait impl，key是trait的名字(带泛型参数)
+    pub fn trait_impls(&self) -> &HashMap<String, ItemImpl> {
+        &self.impl_traits
+    }
+
+    /// `#[declare(abstract)]`
+    pub fn is_abstract(&self) -> bool {
+        self.options.is_abstract
+    }
+
+    /// 线性化之后的祖先，不包括自己，越靠前越具体
+    pub fn ancestors(&self) -> &[String] {
+        &self.ancestry.linearization
+    }
+
+    #[allow(dead_code)]
+    fn dbg_print(&self) {
+        let mixin_name = self.name.to_string();
+        dbg!("=========================", mixin_name);
+        dbg!(self.declaration.to_token_stream().to_string());
+        for block in self.extensions.iter() {
+            dbg!(block.to_token_stream().to_string());
+        }
+        for item_impl in self.impl_traits.iter() {
+            dbg!(item_impl.0, item_impl.1.to_token_stream().to_string());
+        }
+    }
+
+    pub(crate) fn add_overwrite_impls(&mut self, item_impl: &ItemImpl) {
+        self.overwrite_impls.push(item_impl.clone());
+    }
+
+    //已有的impl块里同名(同cfg)的fn会被新块里的覆盖
+    pub(crate) fn add_extension(&mut self, item_impl: &ItemImpl) {
+        let keys: HashSet<String> = impl_fn_keys(item_impl).into_iter().collect();
+        remove_fns(&mut self.extensions, |k| keys.contains(k));
+        if !item_impl.items.is_empty() {
+            self.extensions.push(item_impl.clone());
+        }
+    }
+
+    /// 类型连同它所有的impl，也就是 `#[declare]` 输出的内容
+    pub fn to_token_stream(&self) -> TokenStream {
+        if self.declaration.is_none() {
+            return Error::InvalidExpansion.to_compile_error();
+        }
+        let derive_input = self.declaration.clone().unwrap();
+
+        //mixin类型可能只是用来insert的，get/set和继承来的方法也不一定都用得到
+        let mut stream: TokenStream = quote! {
+            #[allow(dead_code)]
+            #derive_input
+        };
+
+        //for impl fn，继承过来的impl块在insert的时候已经换成了目标类型
+        for block in self.extensions.iter() {
+            let mut block = block.clone();
+            MixinAttrStripper.visit_item_impl_mut(&mut block);
+            stream.extend(quote! {
+                #[allow(dead_code)]
+                #block
+            });
+        }
+
+        //for impl trait
+        for (_, trait_impl) in self.impl_traits.iter() {
+            let mut trait_impl = trait_impl.clone();
+            MixinAttrStripper.visit_item_impl_mut(&mut trait_impl);
+            stream.extend(trait_impl.to_token_stream())
+        }
+        stream
+    }
+}
+
+//registry里存的是源码字符串，正常情况下一定能解析回来
+impl TryFrom<&Mixin> for MixinCtx {
+    type Error = syn::Error;
+
+    fn try_from(value: &Mixin) -> Result<Self, Self::Error> {
+        let name = Ident::new(&value.name, Span::call_site());
+        let declaration = value
+            .declaration
+            .as_ref()
+            .map(|declaration| syn::parse_str::<DeriveInput>(declaration))
+            .transpose()?;
+
+        let extensions = value
+            .extensions
+            .iter()
+            .map(|block| syn::parse_str::<ItemImpl>(block))
+            .collect::<Result<_, _>>()?;
+
+        let overwrite_impls = value
+            .overwrite_impls
+            .iter()
+            .map(|block| syn::parse_str::<ItemImpl>(block))
+            .collect::<Result<_, _>>()?;
+
+        let mut impl_traits = HashMap::new();
+        for ov in value.impl_traits.iter() {
+            let trait_impl = syn::parse_str::<ItemImpl>(ov.1)?;
+            impl_traits.insert(ov.0.clone(), trait_impl);
+        }
+
+        let mut over_traits = HashMap::new();
+        for ov in value.over_traits.iter() {
+            let ov_trait = syn::parse_str::<ItemImpl>(ov.1)?;
+            over_traits.insert(ov.0.clone(), ov_trait);
+        }
+
+        Ok(MixinCtx {
+            name,
+            mixin_type: value.mixin_type.clone(),
+            declaration,
+            extensions,
+            overwrite_impls,
+            impl_traits,
+            over_traits,
+            options: value.options.clone(),
+            required: value
+                .required
+                .iter()
+                .map(|required| syn::parse_str::<ForeignItemFn>(required))
+                .collect::<Result<_, _>>()?,
+            ancestry: value.ancestry.clone(),
+        })
+    }
+}
+
+//从registry取出来的mixin解析失败时，错误指向用到它的地方
+pub(crate) fn load_mixin(mixin: &Mixin, at: impl ToTokens) -> Result<MixinCtx, Error> {
+    MixinCtx::try_from(mixin).map_err(|e| {
+        syn::Error::new_spanned(
+            at,
+            format!("can't load mixin `{}` from the registry: {}", mixin.name, e),
+        )
+        .into()
+    })
+}
+
+impl From<&MixinCtx> for Mixin {
+    fn from(value: &MixinCtx) -> Self {
+        let name: Ident = value.name.clone();
+
+        let declaration = value
+            .declaration
+            .as_ref()
+            .map(|declaration| declaration.to_token_stream().to_string());
+
+        //for impl blocks
+        let extensions: Vec<String> = value
+            .extensions
+            .iter()
+            .map(|block| block.to_token_stream().to_string())
+            .collect();
+
+        //for overwrite_impls
+        let overwrite_impls: Vec<String> = value
+            .overwrite_impls
+            .iter()
+            .map(|block| block.to_token_stream().to_string())
+            .collect();
+
+        //for impl trait
+        let mut impl_traits = HashMap::new();
+        for (trait_name, trait_impl) in value.impl_traits.iter() {
+            impl_traits.insert(trait_name.clone(), trait_impl.to_token_stream().to_string());
+        }
+
+        //for over_traits
+        let mut over_traits = HashMap::new();
+        for (trait_name, trait_impl) in value.over_traits.iter() {
+            over_traits.insert(trait_name.clone(), trait_impl.to_token_stream().to_string());
+        }
+
+        Mixin {
+            name: name.to_string(),
+            mixin_type: value.mixin_type.clone(),
+            declaration,
+            extensions,
+            overwrite_impls,
+            impl_traits,
+            over_traits,
+            options: value.options.clone(),
+            required: value
+                .required
+                .iter()
+                .map(|required| required.to_token_stream().to_string())
+                .collect(),
+            ancestry: value.ancestry.clone(),
+        }
+    }
+}
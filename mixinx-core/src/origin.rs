@@ -0,0 +1,112 @@
+//! 继承关系: C3线性化，以及菱形继承时同一个fn/trait留哪一份
+use std::collections::HashMap;
+
+use crate::mixin::Mixin;
+
+//继承关系: 线性化之后的祖先，以及每个方法/trait是从哪个类型来的
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Ancestry {
+    //直接insert(以及insert_fields)的类型，按参数的顺序
+    pub(crate) parents: Vec<String>,
+    //C3线性化的结果，不包括自己，越靠前越具体
+    pub(crate) linearization: Vec<String>,
+    //fn的key => 定义(或者overwrite)这个fn的类型
+    pub(crate) fn_origins: HashMap<String, String>,
+    //trait的key => 实现这个trait的类型
+    pub(crate) trait_origins: HashMap<String, String>,
+    //字段名 => 声明这个字段的类型
+    pub(crate) field_origins: HashMap<String, String>,
+}
+
+//C3线性化: 每个父类型连同它自己的线性化结果，再加上父类型的列表，依次取不出现在其他序列尾部的头。
+//取不出来说明两个父类型对祖先的先后顺序有分歧，返回分歧的那几个类型
+pub(crate) fn c3_linearize(
+    parents: &[String],
+    data: &HashMap<String, Mixin>,
+) -> Result<Vec<String>, Vec<String>> {
+    let mut sequences: Vec<Vec<String>> = parents
+        .iter()
+        .map(|parent| {
+            let mut seq = vec![parent.clone()];
+            if let Some(mixin) = data.get(parent) {
+                seq.extend(mixin.ancestry.linearization.iter().cloned());
+            }
+            seq
+        })
+        .collect();
+    sequences.push(parents.to_vec());
+    let mut result: Vec<String> = Vec::new();
+    loop {
+        sequences.retain(|seq| !seq.is_empty());
+        if sequences.is_empty() {
+            return Ok(result);
+        }
+        let head = sequences
+            .iter()
+            .map(|seq| &seq[0])
+            .find(|head| !sequences.iter().any(|seq| seq[1..].contains(head)))
+            .cloned();
+        let Some(head) = head else {
+            let mut heads: Vec<String> = sequences.iter().map(|seq| seq[0].clone()).collect();
+            heads.dedup();
+            return Err(heads);
+        };
+        for seq in sequences.iter_mut() {
+            if seq[0] == head {
+                seq.remove(0);
+            }
+        }
+        result.push(head);
+    }
+}
+
+pub(crate) fn is_ancestor(ancestor: &str, of: &str, data: &HashMap<String, Mixin>) -> bool {
+    data.get(of)
+        .is_some_and(|mixin| mixin.ancestry.linearization.iter().any(|a| a == ancestor))
+}
+
+//同一个fn/trait从两个父类型过来时谁留下
+pub(crate) enum OriginPick {
+    Keep,
+    Replace,
+    //两边各自overwrite了共同祖先的实现，后面的先留下，目标类型没有overwrite的话报错
+    Conflict(String),
+}
+
+pub(crate) struct OriginConflict {
+    pub(crate) key: String,
+    pub(crate) is_trait: bool,
+    pub(crate) paths: (String, String),
+    pub(crate) common: String,
+}
+
+//origins_of取出某个类型的fn_origins或者trait_origins
+pub(crate) fn pick_origin(
+    key: &str,
+    old: &str,
+    new: &str,
+    data: &HashMap<String, Mixin>,
+    origins_of: impl Fn(&Mixin) -> &HashMap<String, String>,
+) -> OriginPick {
+    //菱形继承里同一个祖先的实现只算一次；更具体的(子类型overwrite过的)优先
+    if old == new || is_ancestor(new, old, data) {
+        return OriginPick::Keep;
+    }
+    if is_ancestor(old, new, data) {
+        return OriginPick::Replace;
+    }
+    let common = data
+        .get(old)
+        .into_iter()
+        .flat_map(|m| m.ancestry.linearization.iter())
+        .find(|a| {
+            is_ancestor(a, new, data)
+                && data
+                    .get(a.as_str())
+                    .is_some_and(|m| origins_of(m).get(key).is_some_and(|o| o == *a))
+        });
+    match common {
+        Some(common) => OriginPick::Conflict(common.clone()),
+        None => OriginPick::Replace,
+    }
+}
@@ -0,0 +1,222 @@
+//! [`Registry`]: 宏展开共用的mixin表
+use once_cell::sync::Lazy;
+use proc_macro2::{Ident, Span, TokenStream};
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use syn::{Attribute, DeriveInput};
+
+use crate::declare::{declare_impl, expand_impl, overwrite_impl};
+use crate::enums::insert_fields_impl;
+use crate::insert::insert_impl;
+use crate::mixin::{Mixin, MixinCtx};
+use crate::replay::{step_key, Replayed};
+use crate::Error;
+
+/// 登记过的mixin。通过declare和expand将对应的结构的声明以及impl实现保存起来，然后在insert的时候，将其添加到另外struct的源码上。
+///
+/// 宏用的是 [`Registry::global`]；build.rs、别的宏或者测试里可以自己建一个，互不影响:
+///
+/// ```
+/// use mixinx_core::Registry;
+///
+/// let mut registry = Registry::new();
+/// registry.declare("".parse().unwrap(), "pub struct Person { pub name: String }".parse().unwrap());
+/// let student = registry.insert("Person".parse().unwrap(), "pub struct Student { pub school: String }".parse().unwrap());
+/// assert!(student.to_string().contains("get_person"));
+/// assert_eq!(registry.get("Student").unwrap().ancestors(), ["Person"]);
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    pub(crate) mixins: HashMap<String, Mixin>,
+    //declare/insert失败了的类型，后面用到它们的地方不再重复报错，只提示去看原来的错误
+    pub(crate) failed: HashSet<String>,
+    //提前回放过的宏调用，key是step_key
+    pub(crate) replayed: HashMap<String, Replayed>,
+}
+
+//宏共用的registry，第一次用的时候回放build.rs扫描出来的item
+static GLOBAL: Lazy<Mutex<Registry>> = Lazy::new(|| {
+    let mut registry = Registry::new();
+    registry.replay_build_registry();
+    Mutex::new(registry)
+});
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 一次编译里所有宏展开共用的registry。如果build.rs里用mixinx-build扫描过当前crate，第一次用的时候先回放扫描的结果
+    pub fn global() -> MutexGuard<'static, Registry> {
+        //某个宏panic之后Mutex会被poison，但是里面的数据每次都是算完之后才写进去的，还是完整的，直接拿出来用
+        GLOBAL.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// `#[declare(..)]`: `args` 是属性的参数，`input` 是类型，返回展开的结果，出错的时候是 `compile_error!`
+    pub fn declare(&mut self, args: TokenStream, input: TokenStream) -> TokenStream {
+        self.run_macro("declare", args, input, true, declare_impl)
+    }
+
+    /// `#[expand]`
+    pub fn expand(&mut self, args: TokenStream, input: TokenStream) -> TokenStream {
+        if !args.is_empty() {
+            return syn::Error::new_spanned(args, "#[expand] takes no arguments")
+                .to_compile_error();
+        }
+        self.run_macro(
+            "expand",
+            args,
+            input,
+            false,
+            |registry, _, input, replayed| expand_impl(registry, input, replayed),
+        )
+    }
+
+    /// `#[overwrite]`
+    pub fn overwrite(&mut self, args: TokenStream, input: TokenStream) -> TokenStream {
+        if !args.is_empty() {
+            return syn::Error::new_spanned(args, "#[overwrite] takes no arguments")
+                .to_compile_error();
+        }
+        self.run_macro(
+            "overwrite",
+            args,
+            input,
+            false,
+            |registry, _, input, replayed| overwrite_impl(registry, input, replayed),
+        )
+    }
+
+    /// `#[insert(..)]`
+    pub fn insert(&mut self, args: TokenStream, input: TokenStream) -> TokenStream {
+        self.run_macro("insert", args, input, true, insert_impl)
+    }
+
+    /// `#[insert_fields(..)]`
+    pub fn insert_fields(&mut self, args: TokenStream, input: TokenStream) -> TokenStream {
+        self.run_macro("insert_fields", args, input, true, insert_fields_impl)
+    }
+
+    /// 按名字取出一个mixin，只有 `#[overwrite]` 过的类型还不算
+    pub fn get(&self, name: &str) -> Option<MixinCtx> {
+        self.mixins
+            .get(name)
+            .filter(|mixin| mixin.declaration.is_some())
+            .and_then(|mixin| MixinCtx::try_from(mixin).ok())
+    }
+
+    /// 登记过的mixin的名字，按字母顺序
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .mixins
+            .values()
+            .filter(|mixin| mixin.declaration.is_some())
+            .map(|mixin| mixin.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    //proc macro不知道自己在哪个模块里，registry按类型名登记，别的模块里的同名类型会和前一个混在一起，直接报错。
+    //只有overwrite过的类型还没有声明。同一个类型上叠着的宏(#[insert]后面的#[insert_fields])，前面登记的声明上还留着这个宏
+    pub(crate) fn check_undeclared(&self, macro_name: &str, ident: &Ident) -> Result<(), Error> {
+        let name = ident.to_string();
+        let Some(declaration) = self.mixins.get(&name).and_then(|m| m.declaration.as_ref()) else {
+            return Ok(());
+        };
+        let pending = |input: DeriveInput| {
+            input
+                .attrs
+                .iter()
+                .any(|attr| mixin_macro(attr).as_deref() == Some(macro_name))
+        };
+        if syn::parse_str(declaration).is_ok_and(pending) {
+            return Ok(());
+        }
+        Err(syn::Error::new_spanned(
+            ident,
+            format!(
+                "`{}` is already declared in this crate; mixinx registers types by name, so rename one of them",
+                name
+            ),
+        )
+        .into())
+    }
+
+    //宏的入口: panic也转成编译错误。定义类型的宏(declare/insert/insert_fields)失败时照样输出原来的类型，
+    //并记下这个类型失败了，避免一个错误在整个crate里引起一连串找不到类型/mixin的错误
+    fn run_macro(
+        &mut self,
+        macro_name: &str,
+        args: TokenStream,
+        input: TokenStream,
+        defines_type: bool,
+        expand: fn(
+            &mut Registry,
+            TokenStream,
+            TokenStream,
+            Option<&Replayed>,
+        ) -> Result<TokenStream, Error>,
+    ) -> TokenStream {
+        let item_name = defines_type
+            .then(|| syn::parse2::<DeriveInput>(input.clone()).ok())
+            .flatten()
+            .map(|item| item.ident);
+        //当前宏调用提前回放过的话，带上回放时的信息
+        let replayed = self
+            .replayed
+            .get(&step_key(macro_name, &args.to_string(), &input.to_string()))
+            .cloned();
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            expand(self, args, input.clone(), replayed.as_ref())
+        }));
+        let error = match result {
+            Ok(Ok(output)) => {
+                if let Some(item_name) = item_name {
+                    self.failed.remove(&item_name.to_string());
+                }
+                return output;
+            }
+            Ok(Err(error)) => error.to_compile_error(),
+            Err(payload) => {
+                let reason = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".into());
+                let message = match item_name.as_ref() {
+                    Some(item_name) => {
+                        format!("#[{}] on `{}` panicked: {}", macro_name, item_name, reason)
+                    }
+                    None => format!("#[{}] panicked: {}", macro_name, reason),
+                };
+                let span = item_name
+                    .as_ref()
+                    .map_or_else(Span::call_site, |name| name.span());
+                syn::Error::new(span, message).to_compile_error()
+            }
+        };
+        let mut output = error;
+        if let Some(item_name) = item_name {
+            self.failed.insert(item_name.to_string());
+            output.extend(input);
+        }
+        output
+    }
+}
+
+/// mixinx的宏的属性名
+pub const MIXIN_MACROS: [&str; 5] = ["declare", "insert", "insert_fields", "expand", "overwrite"];
+
+/// 属性是mixinx的宏的话返回宏的名字。只认裸的名字和 `mixinx::` 开头的路径，`#[serde::insert]` 这种别的crate的不算
+pub fn mixin_macro(attr: &Attribute) -> Option<String> {
+    let segments = &attr.path().segments;
+    let name = match segments.len() {
+        1 => &segments[0],
+        2 if segments[0].ident == "mixinx" => &segments[1],
+        _ => return None,
+    };
+    let name = name.ident.to_string();
+    MIXIN_MACROS.contains(&name.as_str()).then_some(name)
+}
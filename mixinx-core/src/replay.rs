@@ -0,0 +1,255 @@
+//! 回放build.rs里mixinx-build扫描出来的item
+use proc_macro2::{Ident, TokenStream};
+use quote::ToTokens;
+use std::collections::HashSet;
+use std::panic::AssertUnwindSafe;
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{DeriveInput, Item, ItemImpl, Meta};
+
+use crate::args::InsertArgs;
+use crate::declare::{declare_impl, expand_impl, overwrite_impl};
+use crate::enums::insert_fields_impl;
+use crate::insert::insert_impl;
+use crate::mixin::Mixin;
+use crate::registry::{mixin_macro, Registry};
+use crate::util::get_name_of_impl;
+use crate::Error;
+
+impl Registry {
+    /// 提前回放一个crate里带mixin宏的item(mixinx-build扫描出来的源码)，和它们在源码里的顺序无关:
+    /// declare -> 它们的expand -> overwrite -> 按依赖顺序insert，每个insert后面跟着它的expand。
+    /// 之后这些item真正展开的时候输出照样从源码生成，但是不再改registry
+    pub fn replay(&mut self, items: &[String]) {
+        let mut types: Vec<DeriveInput> = Vec::new();
+        let mut expands: Vec<ItemImpl> = Vec::new();
+        let mut overwrites: Vec<ItemImpl> = Vec::new();
+        for item in items
+            .iter()
+            .filter_map(|item| syn::parse_str::<Item>(item).ok())
+        {
+            match item {
+                Item::Impl(item_impl) => {
+                    match item_impl.attrs.iter().find_map(mixin_macro).as_deref() {
+                        Some("expand") => expands.push(item_impl),
+                        Some("overwrite") => overwrites.push(item_impl),
+                        _ => {}
+                    }
+                }
+                Item::Struct(_) | Item::Enum(_) | Item::Union(_) => {
+                    if let Ok(input) = syn::parse2::<DeriveInput>(item.to_token_stream()) {
+                        types.push(input);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (declared, mut inserted): (Vec<DeriveInput>, Vec<DeriveInput>) =
+            types.into_iter().partition(|input| {
+                input.attrs.iter().find_map(mixin_macro).as_deref() == Some("declare")
+            });
+        let mut available: HashSet<String> = HashSet::new();
+        for input in declared {
+            let name = input.ident.to_string();
+            self.replay_type(input);
+            self.replay_expands(&name, &mut expands);
+            available.insert(name);
+        }
+        for item_impl in overwrites {
+            self.replay_impl(item_impl);
+        }
+        //insert的参数都已经有了才能回放；剩下的(循环或者找不到的mixin)按源码顺序回放，错误留给真正展开的时候报
+        let defined: HashSet<String> = inserted
+            .iter()
+            .map(|input| input.ident.to_string())
+            .collect();
+        while !inserted.is_empty() {
+            let ready = inserted
+                .iter()
+                .position(|input| {
+                    mixin_deps(input)
+                        .iter()
+                        .all(|dep| available.contains(dep) || !defined.contains(dep))
+                })
+                .unwrap_or(0);
+            let input = inserted.remove(ready);
+            let name = input.ident.to_string();
+            self.replay_type(input);
+            self.replay_expands(&name, &mut expands);
+            available.insert(name);
+        }
+    }
+
+    //MIXINX_REGISTRY是build.rs通过cargo:rustc-env设置的，只对扫描的那个crate生效(tests/examples是别的crate)
+    pub(crate) fn replay_build_registry(&mut self) {
+        let Ok(path) = std::env::var("MIXINX_REGISTRY") else {
+            return;
+        };
+        let Some(file) = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|source| serde_json::from_str::<RegistryFile>(&source).ok())
+        else {
+            return;
+        };
+        if std::env::var("CARGO_CRATE_NAME").ok().as_deref() == Some(file.crate_name.as_str()) {
+            self.replay(&file.items);
+        }
+    }
+
+    //回放某个类型的expand，其它的留着
+    fn replay_expands(&mut self, name: &str, expands: &mut Vec<ItemImpl>) {
+        for item_impl in std::mem::take(expands) {
+            if get_name_of_impl(&item_impl)
+                .ok()
+                .is_some_and(|(impl_name, _)| impl_name == name)
+            {
+                self.replay_impl(item_impl);
+            } else {
+                expands.push(item_impl);
+            }
+        }
+    }
+
+    //一步回放: 记下之前registry里的内容，成功了才算回放过，失败的等真正展开的时候按原来的方式处理
+    fn replay_step(
+        &mut self,
+        macro_name: &str,
+        name: &str,
+        args: TokenStream,
+        input: TokenStream,
+        expand: fn(
+            &mut Registry,
+            TokenStream,
+            TokenStream,
+            Option<&Replayed>,
+        ) -> Result<TokenStream, Error>,
+    ) -> Option<TokenStream> {
+        let key = step_key(macro_name, &args.to_string(), &input.to_string());
+        let before = self.mixins.get(name).cloned();
+        match std::panic::catch_unwind(AssertUnwindSafe(|| expand(self, args, input, None))) {
+            Ok(Ok(output)) => {
+                self.replayed.insert(key, Replayed { before });
+                Some(output)
+            }
+            _ => None,
+        }
+    }
+
+    fn replay_impl(&mut self, mut item_impl: ItemImpl) {
+        let Some(index) = item_impl
+            .attrs
+            .iter()
+            .position(|a| mixin_macro(a).is_some())
+        else {
+            return;
+        };
+        let attr = item_impl.attrs.remove(index);
+        let Ok((name, _)) = get_name_of_impl(&item_impl) else {
+            return;
+        };
+        let input = item_impl.to_token_stream();
+        match mixin_macro(&attr).as_deref() {
+            Some("expand") => self.replay_step(
+                "expand",
+                &name,
+                TokenStream::new(),
+                input,
+                |registry, _, input, replayed| expand_impl(registry, input, replayed),
+            ),
+            _ => self.replay_step(
+                "overwrite",
+                &name,
+                TokenStream::new(),
+                input,
+                |registry, _, input, replayed| overwrite_impl(registry, input, replayed),
+            ),
+        };
+    }
+
+    //类型上的宏按顺序一个一个展开，前一个宏的输出里找到这个类型，再交给下一个宏
+    fn replay_type(&mut self, mut input: DeriveInput) {
+        let name = input.ident.to_string();
+        while let Some(index) = input.attrs.iter().position(|a| mixin_macro(a).is_some()) {
+            let attr = input.attrs.remove(index);
+            let args = match &attr.meta {
+                Meta::List(list) => list.tokens.clone(),
+                _ => TokenStream::new(),
+            };
+            let macro_name = mixin_macro(&attr).unwrap_or_default();
+            let tokens = input.to_token_stream();
+            let output = match macro_name.as_str() {
+                "declare" => self.replay_step("declare", &name, args, tokens, declare_impl),
+                "insert" => self.replay_step("insert", &name, args, tokens, insert_impl),
+                "insert_fields" => {
+                    self.replay_step("insert_fields", &name, args, tokens, insert_fields_impl)
+                }
+                _ => None,
+            };
+            let Some(output) = output.and_then(|output| syn::parse2::<syn::File>(output).ok())
+            else {
+                return;
+            };
+            let next = output.items.into_iter().find_map(|item| match item {
+                Item::Struct(ref s) if s.ident == name => {
+                    syn::parse2::<DeriveInput>(item.to_token_stream()).ok()
+                }
+                Item::Enum(ref e) if e.ident == name => {
+                    syn::parse2::<DeriveInput>(item.to_token_stream()).ok()
+                }
+                _ => None,
+            });
+            match next {
+                Some(next) => input = next,
+                None => return,
+            }
+        }
+    }
+}
+
+//build.rs里用mixinx_build扫描出来的registry文件: 整个crate里带mixin宏的item，按源码顺序
+#[derive(serde::Deserialize)]
+struct RegistryFile {
+    crate_name: String,
+    items: Vec<String>,
+}
+
+//扫描过、已经提前回放过的宏调用。真正展开的时候输出照样从源码生成，但是不再改registry，
+//registry里已经是整个crate都回放完的结果
+#[derive(Clone)]
+pub(crate) struct Replayed {
+    pub(crate) before: Option<Mixin>, //回放这一步之前，registry里这个类型的内容(比如只有overwrite)
+}
+
+//源码里的token和回放时从字符串重新解析的token空格可能不一样，key里去掉空白
+pub(crate) fn step_key(macro_name: &str, args: &str, input: &str) -> String {
+    format!("{}({}){}", macro_name, args, input)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+//insert/insert_fields的参数里用到的mixin
+fn mixin_deps(input: &DeriveInput) -> Vec<String> {
+    let mut deps = Vec::new();
+    for attr in input.attrs.iter() {
+        let Ok(list) = attr.meta.require_list() else {
+            continue;
+        };
+        match mixin_macro(attr).as_deref() {
+            Some("insert") => {
+                if let Ok(args) = syn::parse2::<InsertArgs>(list.tokens.clone()) {
+                    deps.extend(args.mixins.iter().map(|m| m.name.to_string()));
+                }
+            }
+            Some("insert_fields") => {
+                if let Ok(args) = list.parse_args_with(Punctuated::<Ident, Comma>::parse_terminated)
+                {
+                    deps.extend(args.iter().map(|arg| arg.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    deps
+}
@@ -0,0 +1,340 @@
+//! 把mixin的impl改写到目标类型上: Self、泛型参数以及trait的key
+use proc_macro2::{Group, Ident, Span, TokenStream, TokenTree as TokenTree2};
+use quote::{format_ident, ToTokens};
+use std::collections::{HashMap, HashSet};
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_quote, AngleBracketedGenericArguments, Attribute, DeriveInput, GenericArgument,
+    GenericParam, Generics, ImplItemFn, Item, ItemImpl, Lifetime, Macro, Path, PathArguments, Type,
+    TypeGroup, TypeParen, TypePath,
+};
+
+use crate::util::has_mixin_flag;
+use crate::Error;
+
+//继承到别的类型上的代码里，把mixin自己的名字(Person、Person::new、Person { .. })换成Self。
+//如果确实要引用父类型，可以写成带路径的 self::Person / super::Person / crate::xx::Person，
+//或者在整个fn上加 #[mixin(keep_type)]。
+pub(crate) struct SelfTypeRewriter {
+    name: String,
+    renames: HashMap<String, Ident>, //insert时改名的enum variant, Self::Register => Self::DevRegister
+}
+
+impl SelfTypeRewriter {
+    pub(crate) fn new(name: &str) -> Self {
+        SelfTypeRewriter {
+            name: name.to_string(),
+            renames: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn with_renames(mut self, renames: &[(Ident, Ident)]) -> Self {
+        self.renames = renames
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.clone()))
+            .collect();
+        self
+    }
+
+    fn rewrite_tokens(&self, tokens: TokenStream) -> TokenStream {
+        //宏里面的tokens没办法用syn解析，只能按token替换，跳过 xx::Person 和 x.Person 这种
+        let mut prev_is_path_sep = false;
+        let mut after_self = 0; //Self 以及后面的 : 一共几个token, 3表示 Self::
+        tokens
+            .into_iter()
+            .map(|tt| {
+                let out = match tt {
+                    TokenTree2::Ident(ref idt) if *idt == self.name && !prev_is_path_sep => {
+                        TokenTree2::Ident(Ident::new("Self", idt.span()))
+                    }
+                    TokenTree2::Ident(ref idt)
+                        if after_self == 3 && self.renames.contains_key(&idt.to_string()) =>
+                    {
+                        TokenTree2::Ident(Ident::new(
+                            &self.renames[&idt.to_string()].to_string(),
+                            idt.span(),
+                        ))
+                    }
+                    TokenTree2::Group(ref g) => {
+                        let mut group = Group::new(g.delimiter(), self.rewrite_tokens(g.stream()));
+                        group.set_span(g.span());
+                        TokenTree2::Group(group)
+                    }
+                    _ => tt.clone(),
+                };
+                prev_is_path_sep =
+                    matches!(&tt, TokenTree2::Punct(p) if p.as_char() == ':' || p.as_char() == '.');
+                after_self = match &out {
+                    TokenTree2::Ident(idt) if idt == "Self" => 1,
+                    TokenTree2::Punct(p) if p.as_char() == ':' && after_self > 0 => after_self + 1,
+                    _ => 0,
+                };
+                out
+            })
+            .collect()
+    }
+}
+
+impl VisitMut for SelfTypeRewriter {
+    fn visit_impl_item_fn_mut(&mut self, impl_fn: &mut ImplItemFn) {
+        if has_mixin_flag(&impl_fn.attrs, "keep_type") {
+            return;
+        }
+        visit_mut::visit_impl_item_fn_mut(self, impl_fn);
+    }
+
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        if path.leading_colon.is_none() {
+            if let Some(first) = path.segments.first_mut() {
+                if first.ident == self.name {
+                    first.ident = Ident::new("Self", first.ident.span());
+                    first.arguments = PathArguments::None;
+                }
+            }
+            if path.segments.len() >= 2 && path.segments[0].ident == "Self" {
+                let variant = &mut path.segments[1].ident;
+                if let Some(renamed) = self.renames.get(&variant.to_string()) {
+                    *variant = Ident::new(&renamed.to_string(), variant.span());
+                }
+            }
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        visit_mut::visit_macro_mut(self, mac);
+        mac.tokens = self.rewrite_tokens(mac.tokens.clone());
+    }
+
+    fn visit_item_mut(&mut self, _item: &mut Item) {
+        //fn里面嵌套的item不能用Self，保持原样
+    }
+}
+
+pub(crate) struct MixinAttrStripper;
+
+impl VisitMut for MixinAttrStripper {
+    fn visit_attributes_mut(&mut self, attrs: &mut Vec<Attribute>) {
+        attrs.retain(|attr| !attr.path().is_ident("mixin"));
+    }
+}
+
+//trait的key需要规范化，否则 Handler<Online>、self::Handler<Online>，
+//以及 impl<T> Handler<T> / impl<U> Handler<U> 会被当成不同的trait，overwrite就覆盖不到了。
+pub(crate) fn trait_key(input: &ItemImpl) -> String {
+    let Some((_, path, _)) = &input.trait_ else {
+        return "".into();
+    };
+    let mut path = path.clone();
+    TraitKeyNormalizer::new(&input.generics).visit_path_mut(&mut path);
+    path.to_token_stream().to_string()
+}
+
+pub(crate) struct TraitKeyNormalizer {
+    params: HashMap<String, Ident>,
+    lifetimes: HashMap<String, Lifetime>,
+}
+
+impl TraitKeyNormalizer {
+    pub(crate) fn new(generics: &Generics) -> Self {
+        //impl上的泛型参数按位置重新命名，这样参数名字不同也能得到相同的key
+        let mut params = HashMap::new();
+        let mut lifetimes = HashMap::new();
+        for (i, param) in generics.params.iter().enumerate() {
+            match param {
+                GenericParam::Type(t) => {
+                    params.insert(t.ident.to_string(), format_ident!("__P{}", i));
+                }
+                GenericParam::Const(c) => {
+                    params.insert(c.ident.to_string(), format_ident!("__P{}", i));
+                }
+                GenericParam::Lifetime(l) => {
+                    let lt = Lifetime::new(&format!("'__p{}", i), l.lifetime.span());
+                    lifetimes.insert(l.lifetime.ident.to_string(), lt);
+                }
+            }
+        }
+        TraitKeyNormalizer { params, lifetimes }
+    }
+}
+
+impl VisitMut for TraitKeyNormalizer {
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        //去掉开头的 ::、crate:: 和 self::
+        path.leading_colon = None;
+        while path.segments.len() > 1 {
+            let first = &path.segments[0];
+            if (first.ident == "crate" || first.ident == "self") && first.arguments.is_none() {
+                path.segments = path.segments.clone().into_pairs().skip(1).collect();
+            } else {
+                break;
+            }
+        }
+        if let Some(first) = path.segments.first_mut() {
+            if let Some(renamed) = self.params.get(&first.ident.to_string()) {
+                first.ident = renamed.clone();
+            }
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+
+    fn visit_angle_bracketed_generic_arguments_mut(
+        &mut self,
+        args: &mut AngleBracketedGenericArguments,
+    ) {
+        visit_mut::visit_angle_bracketed_generic_arguments_mut(self, args);
+        //位置参数保持顺序，关联类型的绑定(Item = u8)按名字排序后放在后面
+        let (positional, mut bindings): (Vec<_>, Vec<_>) =
+            args.args.iter().cloned().partition(|arg| {
+                matches!(
+                    arg,
+                    GenericArgument::Lifetime(_)
+                        | GenericArgument::Type(_)
+                        | GenericArgument::Const(_)
+                )
+            });
+        bindings.sort_by_key(|arg| arg.to_token_stream().to_string());
+        args.args = positional.into_iter().chain(bindings).collect();
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        //去掉多余的括号，Box<(dyn A)> 和 Box<dyn A> 是一样的
+        while let Type::Paren(TypeParen { elem, .. }) | Type::Group(TypeGroup { elem, .. }) = ty {
+            *ty = elem.as_ref().clone();
+        }
+        visit_mut::visit_type_mut(self, ty);
+        if let Type::TraitObject(obj) = ty {
+            //dyn A + Send 和 dyn Send + A，以及老写法里不带dyn的都是同一个类型
+            obj.dyn_token.get_or_insert_with(Default::default);
+            let mut bounds: Vec<_> = obj.bounds.iter().cloned().collect();
+            bounds.sort_by_key(|b| b.to_token_stream().to_string());
+            obj.bounds = bounds.into_iter().collect();
+        }
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if let Some(renamed) = self.lifetimes.get(&lifetime.ident.to_string()) {
+            *lifetime = renamed.clone();
+        }
+    }
+}
+
+//把mixin上的trait impl挂到目标类型上：self_ty换成目标类型，目标类型的泛型参数也要合并到impl上。
+//像 impl<T: Into<String>> Handler<T> for Device 这种blanket impl，如果T和目标类型的泛型参数重名，需要先改名。
+pub(crate) fn retarget_impl(trait_impl: &mut ItemImpl, target: &DeriveInput) -> Result<(), Error> {
+    let ty = trait_impl.self_ty.as_ref();
+    let Type::Path(TypePath { path, .. }) = ty else {
+        return Err(
+            syn::Error::new_spanned(ty, "mixin impls must be for a named struct or enum").into(),
+        );
+    };
+
+    //self_ty上用到的泛型参数说明是mixin自己的泛型参数，和目标类型上同名的参数是同一个
+    let mut self_ty_params = HashSet::new();
+    if let Some(PathArguments::AngleBracketed(args)) = path.segments.last().map(|s| &s.arguments) {
+        for arg in args.args.iter() {
+            match arg {
+                GenericArgument::Type(Type::Path(TypePath { path, .. })) => {
+                    if let Some(idt) = path.get_ident() {
+                        self_ty_params.insert(idt.to_string());
+                    }
+                }
+                GenericArgument::Lifetime(lt) => {
+                    self_ty_params.insert(lt.to_string());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let target_params: HashMap<String, &GenericParam> = target
+        .generics
+        .params
+        .iter()
+        .map(|p| (generic_param_name(p), p))
+        .collect();
+    let mut renames = Vec::new();
+    for param in trait_impl.generics.params.iter() {
+        let param_name = generic_param_name(param);
+        if target_params.contains_key(&param_name) && !self_ty_params.contains(&param_name) {
+            renames.push(param_name);
+        }
+    }
+    for param_name in renames {
+        let mut renamer = GenericRenamer::new(&param_name);
+        renamer.visit_item_impl_mut(trait_impl);
+    }
+
+    let name = &target.ident;
+    let (_, ty_generics, _) = target.generics.split_for_impl();
+    *trait_impl.self_ty = parse_quote!(#name #ty_generics);
+
+    let mut params: Vec<GenericParam> = trait_impl.generics.params.iter().cloned().collect();
+    for target_param in target.generics.params.iter() {
+        let target_param_name = generic_param_name(target_param);
+        let same = params
+            .iter_mut()
+            .find(|p| generic_param_name(p) == target_param_name);
+        match (same, target_param) {
+            (None, _) => params.push(target_param.clone()),
+            (Some(GenericParam::Type(p)), GenericParam::Type(t)) => {
+                p.bounds.extend(t.bounds.iter().cloned())
+            }
+            (Some(GenericParam::Lifetime(p)), GenericParam::Lifetime(t)) => {
+                p.bounds.extend(t.bounds.iter().cloned())
+            }
+            _ => {}
+        }
+    }
+    //lifetime参数必须放在最前面
+    params.sort_by_key(|p| !matches!(p, GenericParam::Lifetime(_)));
+    trait_impl.generics.params = params.into_iter().collect();
+    if let Some(where_clause) = target.generics.where_clause.as_ref() {
+        trait_impl
+            .generics
+            .make_where_clause()
+            .predicates
+            .extend(where_clause.predicates.iter().cloned());
+    }
+    Ok(())
+}
+
+pub(crate) fn generic_param_name(param: &GenericParam) -> String {
+    match param {
+        GenericParam::Type(t) => t.ident.to_string(),
+        GenericParam::Const(c) => c.ident.to_string(),
+        GenericParam::Lifetime(l) => l.lifetime.to_string(),
+    }
+}
+
+//把impl上的某个泛型参数改名，避免和目标类型的泛型参数冲突
+struct GenericRenamer {
+    from: String,
+    ident: Ident,
+    lifetime: Lifetime,
+}
+
+impl GenericRenamer {
+    fn new(from: &str) -> Self {
+        let bare = from.trim_start_matches('\'');
+        GenericRenamer {
+            from: from.to_string(),
+            ident: format_ident!("__Mixin{}", bare),
+            lifetime: Lifetime::new(&format!("'__mixin_{}", bare), Span::call_site()),
+        }
+    }
+}
+
+impl VisitMut for GenericRenamer {
+    fn visit_ident_mut(&mut self, ident: &mut Ident) {
+        if *ident == self.from {
+            *ident = self.ident.clone();
+        }
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.to_string() == self.from {
+            *lifetime = self.lifetime.clone();
+        }
+    }
+}
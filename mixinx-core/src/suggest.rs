@@ -0,0 +1,130 @@
+//! 找不到mixin的时候给出的提示
+use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
+
+use crate::mixin::Mixin;
+
+//registry里找不到的mixin: 可能是拼错了，也可能是 #[declare] 在后面才展开
+//macro_name是用到这个mixin的宏，顺序的提示跟着它说
+pub(crate) fn no_mixin(
+    macro_name: &str,
+    at: impl ToTokens,
+    name: &str,
+    data: &HashMap<String, Mixin>,
+    failed: &HashSet<String>,
+) -> syn::Error {
+    if failed.contains(name) {
+        return syn::Error::new_spanned(
+            at,
+            format!("`{}` failed to expand, see the error reported on it", name),
+        );
+    }
+    //只有overwrite过的类型还不算是mixin
+    let mut known: Vec<&str> = data
+        .values()
+        .filter(|m| m.declaration.is_some())
+        .map(|m| m.name.as_str())
+        .collect();
+    known.sort_unstable();
+    let mut message = format!("can't find mixin `{}`", name);
+    if let Some(suggestion) = suggest(name, &known) {
+        message.push_str(&format!("; did you mean `{}`?", suggestion));
+    }
+    const SHOWN: usize = 10;
+    if known.is_empty() {
+        message.push_str("\nno mixins are declared at this point");
+    } else {
+        let listed: Vec<String> = known
+            .iter()
+            .take(SHOWN)
+            .map(|k| format!("`{}`", k))
+            .collect();
+        message.push_str(&format!("\nmixins declared so far: {}", listed.join(", ")));
+        if known.len() > SHOWN {
+            message.push_str(&format!(" and {} more", known.len() - SHOWN));
+        }
+    }
+    let order = match macro_name {
+        "expand" => format!(
+            "the #[declare] of `{}` must come before its #[expand] impls",
+            name
+        ),
+        _ => format!(
+            "the #[declare] of `{}` (and its #[expand] impls) must come before this #[{}]",
+            name, macro_name
+        ),
+    };
+    message.push_str(&format!(
+        "\nmixins are registered in the order the compiler expands items: {}, \
+         and a module declared further down the file is expanded later",
+        order
+    ));
+    syn::Error::new_spanned(at, message)
+}
+
+//忽略大小写编辑距离最近的名字，距离不超过较长名字的1/3(至少1)才算拼错
+fn suggest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let lower = name.to_lowercase();
+    known
+        .iter()
+        .map(|k| (edit_distance(&lower, &k.to_lowercase()), *k))
+        .filter(|(d, k)| *d <= (name.chars().count().max(k.chars().count()) / 3).max(1))
+        .min()
+        .map(|(_, k)| k)
+}
+
+//Levenshtein距离，用来给拼错的名字找建议
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(cur).min(row[j])
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("person", "person"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("persn", "person"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("学生", "学校"), 1);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let known = ["Person", "Pet", "Student"];
+        assert_eq!(suggest("Persn", &known), Some("Person"));
+        //忽略大小写
+        assert_eq!(suggest("person", &known), Some("Person"));
+        //短名字至少允许差1
+        assert_eq!(suggest("Pe", &known), Some("Pet"));
+        //Studnet差2，Student有7个字符，7/3=2
+        assert_eq!(suggest("Studnet", &known), Some("Student"));
+        //差得太多不算拼错
+        assert_eq!(suggest("Teacher", &known), None);
+        assert_eq!(suggest("Pxyz", &known), None);
+        //距离一样时按名字排
+        assert_eq!(suggest("Pat", &["Pot", "Pet"]), Some("Pet"));
+        //按字符数算，不是字节数: 3个字符只允许差1
+        assert_eq!(suggest("学生会", &["学校"]), None);
+        assert_eq!(suggest("学生", &["学校"]), Some("学校"));
+    }
+}
@@ -0,0 +1,142 @@
+//! cfg、serde属性以及显示用的小工具
+use proc_macro2::{TokenStream, TokenTree as TokenTree2};
+use quote::{quote, ToTokens};
+use syn::visit_mut::VisitMut;
+use syn::{parse_quote, Attribute, Generics, ItemImpl, Type, TypePath};
+
+use crate::rewrite::{trait_key, TraitKeyNormalizer};
+use crate::Error;
+
+pub(crate) fn is_cfg(attr: &Attribute) -> bool {
+    attr.path().is_ident("cfg")
+}
+
+//把#[cfg(a)] #[cfg(b)]合并成一个谓词，None表示没有cfg，总是生效
+pub(crate) fn cfg_predicate(attrs: &[Attribute]) -> Option<TokenStream> {
+    let preds: Vec<TokenStream> = attrs
+        .iter()
+        .filter(|a| is_cfg(a))
+        .filter_map(|a| a.meta.require_list().ok().map(|l| l.tokens.clone()))
+        .collect();
+    match preds.len() {
+        0 => None,
+        1 => preds.into_iter().next(),
+        _ => Some(quote! { all(#(#preds),*) }),
+    }
+}
+
+//同名的fn/trait可以放在不同的#[cfg]下面，所以key里面要带上cfg，例如 "print#cfg(feature = \"x\")"
+pub(crate) fn cfg_key(base: &str, attrs: &[Attribute]) -> String {
+    match cfg_predicate(attrs) {
+        Some(pred) => format!("{}#cfg({})", base, pred),
+        None => base.to_string(),
+    }
+}
+
+pub(crate) fn key_base(key: &str) -> &str {
+    key.split('#').next().unwrap_or(key)
+}
+
+//#[derive(Serialize)]、#[derive(serde::Deserialize)]、#[cfg_attr(.., derive(Serialize))]
+pub(crate) fn derives_serde(attrs: &[Attribute]) -> bool {
+    fn has_serde_ident(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|tt| match tt {
+            TokenTree2::Ident(idt) => idt == "Serialize" || idt == "Deserialize",
+            TokenTree2::Group(g) => has_serde_ident(g.stream()),
+            _ => false,
+        })
+    }
+    attrs.iter().any(|attr| {
+        (attr.path().is_ident("derive") || attr.path().is_ident("cfg_attr"))
+            && has_serde_ident(attr.meta.to_token_stream())
+    })
+}
+
+//#[serde(..)] 以及 #[cfg_attr(.., serde(..))]
+pub(crate) fn is_serde_attr(attr: &Attribute) -> bool {
+    if attr.path().is_ident("serde") {
+        return true;
+    }
+    attr.path().is_ident("cfg_attr")
+        && attr
+            .meta
+            .to_token_stream()
+            .into_iter()
+            .any(|tt| matches!(tt, TokenTree2::Group(g) if g.stream().into_iter().any(|t| matches!(t, TokenTree2::Ident(i) if i == "serde"))))
+}
+
+//错误信息里显示代码，去掉TokenStream::to_string加的多余空格: fn id (& self) -> u64 => fn id(&self) -> u64
+pub(crate) fn display_tokens(tokens: TokenStream) -> String {
+    let mut text = tokens.to_string();
+    let replacements = [
+        (" (", "("),
+        ("( ", "("),
+        (" )", ")"),
+        ("& ", "&"),
+        (" ,", ","),
+        (" :", ":"),
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ;", ";"),
+    ];
+    for (from, to) in replacements {
+        text = text.replace(from, to);
+    }
+    text.replace(":: ", "::")
+}
+
+//类型用prettyplease格式化: Vec<(u8, String)>、&'a dyn Fn(u8) -> u8
+pub(crate) fn display_type(ty: &Type) -> String {
+    let file: syn::File = parse_quote! { type __T = #ty; };
+    let text = prettyplease::unparse(&file);
+    let text = text.trim_end().trim_end_matches(';');
+    let text = text.strip_prefix("type __T = ").unwrap_or(text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+//比较字段类型用: 多余的括号、开头的 ::/crate::/self::、dyn的bound顺序都不算区别
+pub(crate) fn normalize_type(ty: &Type) -> Type {
+    let mut ty = ty.clone();
+    TraitKeyNormalizer::new(&Generics::default()).visit_type_mut(&mut ty);
+    ty
+}
+
+//#[mixin(...)] 是给mixinx自己看的标记，输出代码之前需要去掉
+pub(crate) fn has_mixin_flag(attrs: &[Attribute], flag: &str) -> bool {
+    attrs.iter().any(|attr| {
+        if !attr.path().is_ident("mixin") {
+            return false;
+        }
+        let mut found = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(flag) {
+                found = true;
+            }
+            Ok(())
+        });
+        found
+    })
+}
+
+//获得impl的name以及trait_name, 如果不是trait，则trait_name返回空字符串
+pub(crate) fn get_name_of_impl(input: &ItemImpl) -> Result<(String, String), Error> {
+    let mut trait_name = trait_key(input);
+    if !trait_name.is_empty() {
+        trait_name = cfg_key(&trait_name, &input.attrs);
+    }
+
+    let ty = input.self_ty.as_ref();
+    //self_ty可能是 self::Device 或者 Device<T>，这里只取最后一段的ident
+    let Some(segment) = (match ty {
+        Type::Path(TypePath { path, .. }) => path.segments.last(),
+        _ => None,
+    }) else {
+        return Err(
+            syn::Error::new_spanned(ty, "mixin impls must be for a named struct or enum").into(),
+        );
+    };
+    let name = segment.ident.to_string();
+
+    Ok((name, trait_name))
+}
@@ -0,0 +1,498 @@
+use mixinx_core::{gen_get_set_impls, mixin_macro, Registry};
+use quote::quote;
+use syn::{DeriveInput, File, Item};
+
+fn items(output: proc_macro2::TokenStream) -> Vec<Item> {
+    syn::parse2::<File>(output).unwrap().items
+}
+
+fn field_names(mixin: &DeriveInput) -> Vec<String> {
+    match &mixin.data {
+        syn::Data::Struct(data) => data
+            .fields
+            .iter()
+            .map(|field| field.ident.as_ref().unwrap().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[test]
+fn test_insert() {
+    let mut registry = Registry::new();
+    registry.declare(
+        quote!(),
+        quote! {
+            pub struct Person {
+                pub name: String,
+            }
+        },
+    );
+    registry.expand(
+        quote!(),
+        quote! {
+            impl Person {
+                pub fn hello(&self) -> String {
+                    format!("hello {}", self.name)
+                }
+            }
+        },
+    );
+    let output = registry.insert(
+        quote!(Person),
+        quote! {
+            pub struct Student {
+                pub school: String,
+            }
+        },
+    );
+    assert!(!output.to_string().contains("compile_error"));
+    assert!(items(output)
+        .iter()
+        .any(|item| matches!(item, Item::Struct(s) if s.ident == "Student")));
+
+    assert_eq!(registry.names(), ["Person", "Student"]);
+    let student = registry.get("Student").unwrap();
+    assert_eq!(student.name(), "Student");
+    assert!(!student.is_enum());
+    assert_eq!(
+        field_names(student.declaration().unwrap()),
+        ["school", "name"]
+    );
+    assert_eq!(student.ancestors(), ["Person"]);
+    let fns: Vec<String> = student
+        .extensions()
+        .iter()
+        .flat_map(|block| block.items.iter())
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(f) => Some(f.sig.ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    assert!(fns.contains(&"hello".to_string()));
+    assert!(fns.contains(&"get_person".to_string()));
+}
+
+#[test]
+fn test_errors() {
+    let mut registry = Registry::new();
+    //找不到的mixin是compile_error，类型照样输出
+    let output = registry.insert(
+        quote!(Persn),
+        quote! {
+            pub struct Student {
+                pub school: String,
+            }
+        },
+    );
+    let text = output.to_string();
+    assert!(text.contains("compile_error"));
+    assert!(text.contains("can't find mixin `Persn`"));
+    assert!(text.contains("struct Student"));
+    assert!(registry.get("Student").is_none());
+
+    let output = registry.expand(
+        quote!(x),
+        quote! {
+            impl Student {}
+        },
+    );
+    assert!(output.to_string().contains("takes no arguments"));
+
+    //泛型的enum mixin，目标类型没有声明同名的泛型参数
+    registry.declare(quote!(), quote! { pub enum Reply<T> { Value(T) } });
+    let output = registry.insert(quote!(Reply), quote! { pub enum Response { Empty } });
+    assert!(output
+        .to_string()
+        .contains("`Reply` is generic over `T`; declare the same parameters on `Response`"));
+
+    //struct和enum不能混着insert
+    registry.declare(quote!(), quote! { pub struct Tag { pub tag: u8 } });
+    let output = registry.insert(quote!(Tag), quote! { pub enum Event { Start } });
+    assert!(output
+        .to_string()
+        .contains("`Tag` is a struct, its fields can't be inserted into the enum `Event`"));
+    registry.declare(quote!(), quote! { pub enum Level { Low } });
+    let output = registry.insert(quote!(Level), quote! { pub struct Sensor {} });
+    assert!(output
+        .to_string()
+        .contains("`Level` is an enum, its variants can't be inserted into the struct `Sensor`"));
+
+    //泛型的struct mixin，abstract的也一样
+    registry.declare(quote!(abstract), quote! { pub struct G<T> { pub t: T } });
+    let output = registry.insert(quote!(G), quote! { pub struct H {} });
+    assert!(output
+        .to_string()
+        .contains("`G` is generic over `T`; declare the same parameters on `H`"));
+    let output = registry.insert(quote!(G), quote! { pub struct K<T> {} });
+    assert!(!output.to_string().contains("compile_error"));
+
+    //allow_insert_in的相对路径永远匹配不上module_path!()
+    for path in ["self::devices", "super"] {
+        let output = registry.declare(
+            quote!(allow_insert_in = #path),
+            quote! { pub struct Device {} },
+        );
+        assert!(output
+            .to_string()
+            .contains("`allow_insert_in` needs a path from the crate root"));
+    }
+
+    //同一个mixin写了两次
+    registry.declare(quote!(), quote! { pub enum Msg { Ping(u8) } });
+    let output = registry.insert(
+        quote!(Msg, nest, Msg, nest = Other),
+        quote! { pub enum Packet {} },
+    );
+    assert!(output.to_string().contains("mixin `Msg` is inserted twice"));
+
+    //rename的新名字和目标类型已有的variant撞了
+    let output = registry.insert(
+        quote!(Msg, rename(Ping = Pong)),
+        quote! { pub enum Packet { Pong(String) } },
+    );
+    assert!(output
+        .to_string()
+        .contains("`rename(Ping = Pong)` on `Msg` gives `Pong (u8)`, a name that collides with"));
+}
+
+#[test]
+fn test_ctor_conflict() {
+    let mut registry = Registry::new();
+    for name in ["Person", "Pet"] {
+        let ident = quote::format_ident!("{}", name);
+        registry.declare(quote!(), quote! { pub struct #ident { pub name: String } });
+        registry.expand(
+            quote!(),
+            quote! {
+                impl #ident {
+                    pub fn new(name: String) -> Self {
+                        Self { name }
+                    }
+                }
+            },
+        );
+    }
+    //两个父类型都有new，目标类型没有overwrite就报错
+    let output = registry.insert(quote!(Person, Pet), quote! { pub struct Owner {} });
+    assert!(output
+        .to_string()
+        .contains("`Person` and `Pet` give different constructors `new`"));
+
+    registry.overwrite(
+        quote!(),
+        quote! {
+            impl Keeper {
+                pub fn new(name: String) -> Self {
+                    Self { name }
+                }
+            }
+        },
+    );
+    let output = registry.insert(quote!(Person, Pet), quote! { pub struct Keeper {} });
+    assert!(!output.to_string().contains("compile_error"));
+
+    //菱形继承: 两条路径各自overwrite了Person::new
+    for name in ["Left", "Right"] {
+        let ident = quote::format_ident!("{}", name);
+        registry.overwrite(
+            quote!(),
+            quote! {
+                impl #ident {
+                    pub fn new(name: String) -> Self {
+                        Self { name }
+                    }
+                }
+            },
+        );
+        registry.insert(quote!(Person), quote! { pub struct #ident {} });
+    }
+    let output = registry.insert(quote!(Left, Right), quote! { pub struct Bottom {} });
+    assert!(output
+        .to_string()
+        .contains("`Left` and `Right` give different constructors `new`"));
+}
+
+#[test]
+fn test_field_types() {
+    let mut registry = Registry::new();
+    registry.declare(
+        quote!(),
+        quote! {
+            pub struct Tags {
+                pub tags: Vec<(String)>,
+                pub hook: Box<dyn Send + Fn(u8) -> u8>,
+                pub id: ::core::primitive::u64,
+            }
+        },
+    );
+    //写法不一样的同一个类型不算冲突
+    let output = registry.insert(
+        quote!(Tags),
+        quote! {
+            pub struct Post {
+                pub tags: Vec<String>,
+                pub hook: Box<dyn Fn(u8) -> u8 + Send>,
+                pub id: core::primitive::u64,
+            }
+        },
+    );
+    assert!(!output.to_string().contains("compile_error"));
+
+    let output = registry.insert(
+        quote!(Tags),
+        quote! {
+            pub struct Page {
+                pub tags: Vec<&'static str>,
+            }
+        },
+    );
+    assert!(output.to_string().contains(
+        "field `tags: Vec<(String)>` from `Tags` conflicts with `tags: Vec<&'static str>` in `Page`"
+    ));
+
+    let graph: serde_json::Value = serde_json::from_str(&registry.graph_json()).unwrap();
+    let post = &graph["mixins"][0];
+    assert_eq!(post["name"], "Post");
+    assert_eq!(post["fields"][1]["type"], "Box<dyn Fn(u8) -> u8 + Send>");
+}
+
+#[test]
+fn test_failed_declare() {
+    let mut registry = Registry::new();
+    //declare失败时类型照样输出，后面用到它的地方只提示一次去看原来的错误
+    let output = registry.declare(quote!(bogus), quote! { pub struct Broken { pub a: u8 } });
+    let text = output.to_string();
+    assert!(text.contains("unknown declare option"));
+    assert!(text.contains("pub struct Broken"));
+    let output = registry.insert(quote!(Broken), quote! { pub struct User {} });
+    let text = output.to_string();
+    assert_eq!(
+        text.matches("`Broken` failed to expand, see the error reported on it")
+            .count(),
+        1
+    );
+    assert!(!text.contains("can't find mixin"));
+
+    //registry还能接着用
+    registry.declare(quote!(), quote! { pub struct Person { pub name: String } });
+    let output = registry.insert(quote!(Person), quote! { pub struct Student {} });
+    assert!(!output.to_string().contains("compile_error"));
+    assert_eq!(registry.names(), ["Person", "Student"]);
+
+    //改好了重新declare，之前失败的记录也去掉
+    registry.declare(quote!(), quote! { pub struct Broken { pub a: u8 } });
+    let output = registry.insert(quote!(Broken), quote! { pub struct User {} });
+    assert!(!output.to_string().contains("compile_error"));
+    assert_eq!(
+        field_names(registry.get("User").unwrap().declaration().unwrap()),
+        ["a"]
+    );
+}
+
+#[test]
+fn test_unknown_mixin() {
+    let mut registry = Registry::new();
+    let output = registry.insert(quote!(Person), quote! { pub struct Student {} });
+    assert!(output
+        .to_string()
+        .contains("no mixins are declared at this point"));
+
+    for name in ["Person", "Pet", "Teacher"] {
+        let ident = quote::format_ident!("{}", name);
+        registry.declare(quote!(), quote! { pub struct #ident {} });
+    }
+    let output = registry.insert(quote!(Persn), quote! { pub struct Student {} });
+    let text = output.to_string();
+    assert!(text.contains("can't find mixin `Persn`; did you mean `Person`?"));
+    assert!(text.contains("mixins declared so far: `Person`, `Pet`, `Teacher`"));
+    assert!(text.contains("must come before this #[insert]"));
+
+    let output = registry.insert(quote!(Robot), quote! { pub struct Student {} });
+    let text = output.to_string();
+    assert!(text.contains("can't find mixin `Robot`"));
+    assert!(!text.contains("did you mean"));
+
+    let output = registry.insert_fields(quote!(Persn), quote! { pub enum Event { Start } });
+    assert!(output
+        .to_string()
+        .contains("must come before this #[insert_fields]"));
+
+    //expand的提示说的是declare要在expand前面
+    let output = registry.expand(quote!(), quote! { impl Persn {} });
+    let text = output.to_string();
+    assert!(text.contains("did you mean `Person`?"));
+    assert!(text.contains("the #[declare] of `Persn` must come before its #[expand] impls"));
+}
+
+#[test]
+fn test_mixin_macro() {
+    let attrs: Vec<syn::Attribute> = syn::parse_quote! {
+        #[insert(Person)]
+        #[mixinx::insert(Person)]
+        #[::mixinx::declare]
+        #[serde::insert]
+        #[other::mixinx::expand]
+        #[derive(Debug)]
+    };
+    let names: Vec<Option<String>> = attrs.iter().map(mixin_macro).collect();
+    assert_eq!(
+        names,
+        [
+            Some("insert".to_string()),
+            Some("insert".to_string()),
+            Some("declare".to_string()),
+            None,
+            None,
+            None
+        ]
+    );
+}
+
+#[test]
+fn test_separate_registries() {
+    let mut a = Registry::new();
+    a.declare(quote!(), quote! { pub struct Person { pub name: String } });
+    let b = Registry::new();
+    assert!(a.get("Person").is_some());
+    assert!(b.get("Person").is_none());
+    assert!(b.names().is_empty());
+}
+
+#[test]
+fn test_replay() {
+    //顺序反过来: insert在declare前面，expand在最后
+    let items = [
+        "#[insert(Person)] pub struct Student { pub school: String }",
+        "#[overwrite] impl Student { pub fn hello(&self) -> String { \"student\".into() } }",
+        "#[declare] pub struct Person { pub name: String }",
+        "#[expand] impl Person { pub fn hello(&self) -> String { self.name.clone() } }",
+    ]
+    .map(String::from);
+    let mut registry = Registry::new();
+    registry.replay(&items);
+    let student = registry.get("Student").unwrap();
+    assert_eq!(
+        field_names(student.declaration().unwrap()),
+        ["school", "name"]
+    );
+
+    //真正展开的时候还是输出源码里的item，不再改registry。和回放的是同一个item，token一样
+    let output = registry.insert(
+        quote!(Person),
+        quote! {
+            pub struct Student { pub school: String }
+        },
+    );
+    let text = output.to_string();
+    assert!(!text.contains("compile_error"));
+    assert!(text.contains("\"student\""));
+    assert_eq!(registry.names(), ["Person", "Student"]);
+}
+
+#[test]
+fn test_get_set() {
+    let input: DeriveInput = syn::parse_quote! {
+        pub struct EventMeta {
+            pub timestamp: u64,
+        }
+    };
+    let block = gen_get_set_impls(&input).unwrap();
+    let text = quote!(#block).to_string();
+    assert!(text.contains("get_event_meta"));
+    assert!(text.contains("set_event_meta"));
+
+    let unit: DeriveInput = syn::parse_quote! { pub struct Marker; };
+    assert!(gen_get_set_impls(&unit).is_none());
+}
+
+#[test]
+fn test_graph() {
+    let mut registry = Registry::new();
+    registry.declare(
+        quote!(abstract),
+        quote! {
+            pub struct Tags {
+                pub tags: Vec<String>,
+            }
+        },
+    );
+    registry.insert(
+        quote!(Tags),
+        quote! {
+            pub struct Post {
+                pub title: String,
+            }
+        },
+    );
+    let graph: serde_json::Value = serde_json::from_str(&registry.graph_json()).unwrap();
+    assert_eq!(graph["mixins"][0]["name"], "Post");
+    assert_eq!(graph["mixins"][0]["parents"], serde_json::json!(["Tags"]));
+    assert_eq!(graph["mixins"][0]["fields"][1]["type"], "Vec<String>");
+    assert_eq!(graph["mixins"][0]["fields"][1]["from"], "Tags");
+    assert_eq!(graph["mixins"][1]["abstract"], true);
+
+    let dot = registry.graph_dot();
+    assert!(dot.contains("\"Post\" -> \"Tags\";"));
+    assert!(dot.contains("tags: Vec\\<String\\> (Tags)\\l"));
+    assert!(dot.contains("Tags (abstract)"));
+
+    assert!(registry.graph(quote!()).to_string().starts_with('"'));
+    assert!(registry
+        .graph(quote!(svg))
+        .to_string()
+        .contains("compile_error"));
+}
+
+#[test]
+fn test_same_name() {
+    let mut registry = Registry::new();
+    registry.declare(quote!(), quote! { pub struct Person { pub name: String } });
+    //别的模块里的同名类型不会合并到前一个里
+    let output = registry.declare(quote!(), quote! { pub struct Person { pub age: u8 } });
+    assert!(output
+        .to_string()
+        .contains("`Person` is already declared in this crate"));
+    assert_eq!(
+        field_names(registry.get("Person").unwrap().declaration().unwrap()),
+        ["name"]
+    );
+    registry.insert(
+        quote!(Person),
+        quote! { pub struct Worker { pub shift: u8 } },
+    );
+    let output = registry.insert(
+        quote!(Person),
+        quote! { pub struct Worker { pub desk: u8 } },
+    );
+    assert!(output
+        .to_string()
+        .contains("`Worker` is already declared in this crate"));
+
+    //overwrite在insert前面，登记的时候还没有声明
+    registry.overwrite(quote!(), quote! { impl Manager { pub fn hello(&self) {} } });
+    let output = registry.insert(
+        quote!(Person),
+        quote! { pub struct Manager { pub team: u8 } },
+    );
+    assert!(!output.to_string().contains("compile_error"));
+
+    //同一个enum上的 #[insert] 和后面的 #[insert_fields]
+    registry.declare(quote!(), quote! { pub struct Meta { pub id: u64 } });
+    registry.declare(quote!(), quote! { pub enum Base { Ping {} } });
+    let output = registry.insert(
+        quote!(Base),
+        quote! {
+            #[insert_fields(Meta)]
+            pub enum Frame { Pong {} }
+        },
+    );
+    assert!(output.to_string().contains("insert_fields"));
+    let output =
+        registry.insert_fields(quote!(Meta), quote! { pub enum Frame { Pong {}, Ping {} } });
+    assert!(!output.to_string().contains("compile_error"));
+    let output = registry.insert_fields(quote!(Meta), quote! { pub enum Frame { Start {} } });
+    assert!(output
+        .to_string()
+        .contains("`Frame` is already declared in this crate"));
+}
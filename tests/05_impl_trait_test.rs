@@ -108,6 +108,11 @@ mod tests {
         let e_str = serde_json::to_string(&e).unwrap();
         println!("e_str = {}", e_str);
 
+        //Human的方法也继承到了Employee上
+        e.set_age(31);
+        assert_eq!(e.get_age(), 31);
+        assert_eq!(e.get_person().age, 31);
+
         print_human_age(e);
         print_human_age(p);
         print_human_age(s);
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::tests::{ap::APDevice, device::Device};
+    #[allow(dead_code)]
     trait Handler<T> {
         fn handle(&self, t: T) -> String;
     }
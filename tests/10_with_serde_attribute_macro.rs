@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::tests::{ap::APDevice, device::Device};
+    #[allow(dead_code)]
     trait Handler<T> {
         fn handle(&self, t: T) -> String;
     }
@@ -41,7 +42,7 @@ mod tests {
 
         //Device和APPersist中有重复的字段，需要在生成代码的时候避免重复的字段
         #[insert(Device, APPersist)]
-        #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)] //如果前面的类型添加了serde属性，insert的目标对象必须要实现Deserialize和Seialize
+        #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)] //目标对象没有derive Deserialize和Serialize的时候，mixin字段上的serde属性会被去掉
         pub struct APDevice<T: Clone> {
             pub client_num: u32,
             pub content: T,
@@ -38,6 +38,7 @@ mod tests {
     }
     #[insert(DeviceMessage)]
     #[derive(Clone, Debug)]
+    #[allow(clippy::upper_case_acronyms)]
     pub enum APMSG {
         Register(String),
         Reset(i32),
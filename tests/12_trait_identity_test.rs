@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    pub trait Handler<T> {
+        fn handle(&self, t: T) -> String;
+    }
+
+    pub trait Named<T> {
+        fn named(&self, t: T) -> String;
+    }
+
+    pub trait Source {
+        type Item;
+        fn first(&self) -> Option<Self::Item>;
+    }
+
+    pub struct Online {}
+    pub struct Offline {}
+
+    #[declare]
+    pub struct Device {
+        pub name: String,
+    }
+
+    #[expand]
+    impl Handler<Online> for Device {
+        fn handle(&self, _t: Online) -> String {
+            format!("Device Handle Online {}", self.name)
+        }
+    }
+
+    #[expand]
+    impl Handler<Offline> for Device {
+        fn handle(&self, _t: Offline) -> String {
+            format!("Device Handle Offline {}", self.name)
+        }
+    }
+
+    //blanket impl，目标类型也有同名的泛型参数T
+    #[expand]
+    impl<T: Into<String>> Named<T> for Device {
+        fn named(&self, t: T) -> String {
+            format!("{} {}", t.into(), self.name)
+        }
+    }
+
+    #[expand]
+    impl Source for Device {
+        type Item = char;
+        fn first(&self) -> Option<char> {
+            self.name.chars().next()
+        }
+    }
+
+    //写法和expand时不一样，但是是同一个trait，需要覆盖掉Device上的实现，否则会有两个impl
+    #[overwrite]
+    impl self::Handler<Online> for APDevice {
+        fn handle(&self, _t: Online) -> String {
+            format!("APDevice Handle Online {}", self.name)
+        }
+    }
+
+    #[overwrite]
+    impl<U: Into<String>> self::Named<U> for APDevice {
+        fn named(&self, t: U) -> String {
+            format!("APDevice {} {}", t.into(), self.name)
+        }
+    }
+
+    #[insert(Device)]
+    pub struct APDevice {}
+
+    #[insert(Device)]
+    pub struct Wrapper<T: Clone> {
+        pub content: T,
+    }
+
+    fn handle_online(handler: &impl Handler<Online>) -> String {
+        handler.handle(Online {})
+    }
+
+    fn handle_offline(handler: &impl Handler<Offline>) -> String {
+        handler.handle(Offline {})
+    }
+
+    #[test]
+    fn test_mixin() {
+        let d = Device {
+            name: "Device".into(),
+        };
+        assert_eq!(handle_online(&d), "Device Handle Online Device");
+        assert_eq!(d.named("hello"), "hello Device");
+
+        let ap = APDevice {
+            name: "APDevice".into(),
+        };
+        assert_eq!(handle_online(&ap), "APDevice Handle Online APDevice");
+        assert_eq!(handle_offline(&ap), "Device Handle Offline APDevice");
+        assert_eq!(ap.named("hello"), "APDevice hello APDevice");
+        assert_eq!(ap.first(), Some('A'));
+
+        let w = Wrapper {
+            name: "Wrapper".into(),
+            content: 1u8,
+        };
+        assert_eq!(w.content, 1);
+        assert_eq!(handle_online(&w), "Device Handle Online Wrapper");
+        assert_eq!(w.named("hi"), "hi Wrapper");
+        assert_eq!(w.first(), Some('W'));
+    }
+}
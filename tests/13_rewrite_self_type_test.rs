@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+
+    pub trait Older {
+        fn older_than(&self, other: &Self) -> bool;
+    }
+
+    #[declare]
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Person {
+        pub name: String,
+        pub age: i32,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn new(n: String) -> Person {
+            Person { name: n, age: 0 }
+        }
+
+        pub fn with_age(&self, age: i32) -> Person {
+            let mut p = Person::new(self.name.clone());
+            p.age = age;
+            p
+        }
+
+        pub fn describe(&self) -> String {
+            format!("{:?}", Person::new(self.name.clone()).age)
+        }
+
+        //带路径的写法表示确实是指父类型，不会被替换
+        pub fn to_parent(&self) -> self::Person {
+            self::Person {
+                name: self.name.clone(),
+                age: self.age,
+            }
+        }
+
+        #[mixin(keep_type)]
+        pub fn parent_of(name: &str) -> Person {
+            Person {
+                name: name.into(),
+                age: 60,
+            }
+        }
+    }
+
+    #[expand]
+    impl Older for Person {
+        fn older_than(&self, other: &Person) -> bool {
+            self.age > other.age
+        }
+    }
+
+    #[insert(Person)]
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Employee {
+        pub name: String,
+    }
+
+    #[insert(Employee)]
+    #[derive(Debug, PartialEq, Clone)]
+    pub struct Manager {}
+
+    #[test]
+    fn test_mixin() {
+        let base = Person::new("base".into()).with_age(1);
+        assert_eq!(base.describe(), "0");
+        assert_eq!(base.to_parent(), Person::parent_of("base").with_age(1));
+
+        let e: Employee = Employee::new("xjplke".into());
+        assert_eq!(e.age, 0);
+        let e2: Employee = e.with_age(30);
+        assert_eq!(e2.age, 30);
+        assert!(e2.older_than(&e));
+        assert_eq!(e.describe(), "0");
+
+        let p: Person = e2.to_parent();
+        assert_eq!(p, e2.get_person());
+        let pp: Person = Employee::parent_of("dad");
+        assert_eq!(pp.age, 60);
+
+        let m: Manager = Manager::new("boss".into()).with_age(50);
+        assert!(m.older_than(&Manager::new("new".into())));
+        assert_eq!(m.get_employee(), Employee::new("boss".into()).with_age(50));
+        let mp: Person = Manager::parent_of("dad");
+        assert_eq!(mp, pp);
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    #[declare]
+    #[derive(Debug, PartialEq)]
+    pub struct Person {
+        pub name: String,
+        pub age: i32,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn new(name: String) -> Person {
+            Person { name, age: 18 }
+        }
+
+        pub fn named(mut name: String, (a, b): (i32, i32)) -> Self {
+            name.push('!');
+            Person { name, age: a + b }
+        }
+
+        pub fn parse(s: &str) -> Result<Person, String> {
+            let (name, age) = s.split_once(':').ok_or("missing `:`")?;
+            let age = age.parse().map_err(|_| format!("bad age `{}`", age))?;
+            Ok(Person {
+                name: name.into(),
+                age,
+            })
+        }
+
+        pub fn adult(name: String, age: i32) -> Option<Self> {
+            (age >= 18).then_some(Person { name, age })
+        }
+    }
+
+    #[expand]
+    impl Default for Person {
+        fn default() -> Self {
+            Person {
+                name: "nobody".into(),
+                age: 1,
+            }
+        }
+    }
+
+    //目标类型自己的字段作为新增参数
+    #[insert(Person)]
+    #[derive(Debug, PartialEq)]
+    pub struct Employee {
+        pub name: String,
+        pub company: String,
+        pub workage: i32,
+    }
+
+    //目标类型自己的字段用默认值填充
+    #[insert(Person, ctor = default)]
+    #[derive(Debug, PartialEq)]
+    pub struct Student {
+        pub school: String,
+    }
+
+    //继续往下继承，Employee::new 已经是合成出来的构造函数
+    #[insert(Employee)]
+    #[derive(Debug, PartialEq)]
+    pub struct Manager {
+        pub reports: u32,
+    }
+
+    #[overwrite]
+    impl Teacher {
+        pub fn new(name: String) -> Self {
+            Teacher {
+                name,
+                age: 40,
+                subject: "math".into(),
+            }
+        }
+    }
+
+    #[insert(Person)]
+    #[derive(Debug, PartialEq)]
+    pub struct Teacher {
+        pub subject: String,
+    }
+
+    #[test]
+    fn test_mixin() {
+        assert_eq!(Person::new("p".into()).age, 18);
+
+        let e = Employee::new("xjplke".into(), "xx".into(), 3);
+        assert_eq!(
+            e,
+            Employee {
+                name: "xjplke".into(),
+                age: 18,
+                company: "xx".into(),
+                workage: 3,
+            }
+        );
+        let e = Employee::named("e".into(), (1, 2), "yy".into(), 4);
+        assert_eq!(e.name, "e!");
+        assert_eq!(e.age, 3);
+        assert_eq!(e.workage, 4);
+
+        let s = Student::new("s".into());
+        assert_eq!(s.age, 18);
+        assert_eq!(s.school, "");
+
+        let d = Employee::default();
+        assert_eq!(d.name, "nobody");
+        assert_eq!(d.age, 1);
+        assert_eq!(d.company, "");
+        let d = Student::default();
+        assert_eq!(d.get_person(), Person::default());
+
+        let m = Manager::new("boss".into(), "xx".into(), 10, 5);
+        assert_eq!(
+            m.get_employee(),
+            Employee::new("boss".into(), "xx".into(), 10)
+        );
+        assert_eq!(m.reports, 5);
+        assert_eq!(Manager::default().reports, 0);
+
+        //Result<Self, E>/Option<Self> 也会串起来
+        let e = Employee::parse("e:30", "xx".into(), 1).unwrap();
+        assert_eq!(e.age, 30);
+        assert_eq!(e.company, "xx");
+        assert_eq!(
+            Employee::parse("e", "xx".into(), 1).unwrap_err(),
+            "missing `:`"
+        );
+        assert_eq!(Student::adult("s".into(), 20).unwrap().age, 20);
+        assert!(Student::adult("s".into(), 10).is_none());
+
+        let t = Teacher::new("t".into());
+        assert_eq!(t.age, 40);
+        assert_eq!(t.subject, "math");
+    }
+}
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+
+    pub trait Kind {
+        fn kind(&self) -> &'static str;
+    }
+
+    //测试的时候cfg(test)总是生效，cfg(not(test))总是不生效，用来模拟不同的feature组合
+    #[declare]
+    #[derive(Debug, PartialEq)]
+    pub struct Person {
+        pub name: String,
+        #[cfg(not(test))]
+        pub secret: u32,
+        #[cfg(test)]
+        pub age: i32,
+    }
+
+    #[expand]
+    impl Person {
+        #[cfg(test)]
+        pub fn mode(&self) -> &'static str {
+            "on"
+        }
+
+        #[cfg(not(test))]
+        pub fn mode(&self) -> &'static str {
+            "off"
+        }
+    }
+
+    #[expand]
+    #[cfg(not(test))]
+    impl Person {
+        pub fn secret(&self) -> u32 {
+            self.secret
+        }
+    }
+
+    #[expand]
+    #[cfg(test)]
+    impl Kind for Person {
+        fn kind(&self) -> &'static str {
+            "enabled"
+        }
+    }
+
+    #[expand]
+    #[cfg(not(test))]
+    impl Kind for Person {
+        fn kind(&self) -> &'static str {
+            "disabled"
+        }
+    }
+
+    //自己的name在当前cfg下不存在，需要用Person的name补上
+    #[insert(Person)]
+    #[derive(Debug, PartialEq)]
+    pub struct Employee {
+        #[cfg(not(test))]
+        pub name: u64,
+        #[cfg(test)]
+        pub company: String,
+    }
+
+    #[insert(Employee)]
+    #[derive(Debug, PartialEq)]
+    pub struct Manager {}
+
+    #[test]
+    fn test_mixin() {
+        let p = Person {
+            name: "p".into(),
+            age: 1,
+        };
+        assert_eq!(p.mode(), "on");
+        assert_eq!(p.kind(), "enabled");
+
+        let mut e = Employee {
+            name: "e".into(),
+            age: 2,
+            company: "xx".into(),
+        };
+        assert_eq!(e.mode(), "on");
+        assert_eq!(e.kind(), "enabled");
+        assert_eq!(
+            e.get_person(),
+            Person {
+                name: "e".into(),
+                age: 2
+            }
+        );
+        e.set_person(&p);
+        assert_eq!(e.name, "p");
+
+        let m = Manager {
+            name: "m".into(),
+            age: 3,
+            company: "yy".into(),
+        };
+        assert_eq!(m.get_employee().company, "yy");
+        assert_eq!(m.kind(), "enabled");
+        assert_eq!(m.mode(), "on");
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use mixinx::{declare, expand, insert};
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = pin!(f);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+                return v;
+            }
+        }
+    }
+
+    #[async_trait]
+    pub trait Loader {
+        async fn load(&self) -> String;
+    }
+
+    /// # Safety
+    /// 只是用来测试unsafe impl
+    pub unsafe trait Zeroable {}
+
+    #[declare]
+    #[derive(Debug, PartialEq)]
+    pub struct Person {
+        pub name: String,
+        pub age: i32,
+    }
+
+    /// Person的一些方法
+    #[expand]
+    #[allow(clippy::needless_lifetimes)]
+    impl Person {
+        /// 名字的引用
+        pub fn name_ref<'a>(&'a self) -> &'a str {
+            &self.name
+        }
+
+        pub const fn max_age() -> i32 {
+            150
+        }
+
+        pub async fn greet(&self) -> String {
+            format!("hello {}", self.name)
+        }
+
+        /// # Safety
+        /// 调用者保证age不为负数
+        pub unsafe fn age_unchecked(&self) -> u32 {
+            self.age as u32
+        }
+    }
+
+    //整个impl块都不生效，继承过去也不能生效
+    #[expand]
+    #[cfg(not(test))]
+    impl Person {
+        pub fn hidden(&self) -> NotExist {
+            NotExist
+        }
+    }
+
+    #[expand]
+    #[async_trait]
+    impl Loader for Person {
+        async fn load(&self) -> String {
+            format!("load {}", self.name)
+        }
+    }
+
+    #[expand]
+    unsafe impl Zeroable for Person {}
+
+    #[insert(Person)]
+    #[derive(Debug, PartialEq)]
+    pub struct Employee {
+        pub company: String,
+    }
+
+    #[insert(Employee)]
+    #[derive(Debug, PartialEq)]
+    pub struct Manager {}
+
+    fn is_zeroable<T: Zeroable>(_t: &T) -> bool {
+        true
+    }
+
+    #[test]
+    fn test_mixin() {
+        let p = Person {
+            name: "p".into(),
+            age: 1,
+        };
+        assert_eq!(Person::max_age(), 150);
+        assert_eq!(p.name_ref(), "p");
+        assert_eq!(block_on(p.greet()), "hello p");
+        assert_eq!(block_on(p.load()), "load p");
+        assert_eq!(unsafe { p.age_unchecked() }, 1);
+        assert!(is_zeroable(&p));
+
+        let e = Employee {
+            name: "e".into(),
+            age: 2,
+            company: "xx".into(),
+        };
+        const MAX: i32 = Employee::max_age();
+        assert_eq!(MAX, 150);
+        assert_eq!(e.name_ref(), "e");
+        assert_eq!(block_on(e.greet()), "hello e");
+        assert_eq!(block_on(e.load()), "load e");
+        assert_eq!(unsafe { e.age_unchecked() }, 2);
+        assert!(is_zeroable(&e));
+
+        let m = Manager {
+            name: "m".into(),
+            age: 3,
+            company: "yy".into(),
+        };
+        assert_eq!(Manager::max_age(), 150);
+        assert_eq!(block_on(m.greet()), "hello m");
+        assert_eq!(block_on(m.load()), "load m");
+        assert_eq!(unsafe { m.age_unchecked() }, 3);
+        assert!(is_zeroable(&m));
+    }
+}
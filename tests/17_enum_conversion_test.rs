@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, insert};
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum DeviceMessage {
+        Register(String),
+        Update { id: u32, value: i32 },
+        Ping,
+    }
+
+    #[insert(DeviceMessage)]
+    #[allow(clippy::upper_case_acronyms)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum APMSG {
+        Register(String),
+        Reset(i32),
+    }
+
+    //继续往下继承，From<DeviceMessage>和is_/as_也会跟着继承
+    #[insert(APMSG)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ControllerMsg {
+        Shutdown,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let msg: APMSG = DeviceMessage::Register("r".into()).into();
+        assert_eq!(msg, APMSG::Register("r".into()));
+        let msg = APMSG::from(DeviceMessage::Update { id: 1, value: 2 });
+        assert_eq!(msg, APMSG::Update { id: 1, value: 2 });
+        assert!(msg.is_device_message());
+        //as_返回variant里的值的引用，多个字段的是tuple
+        assert_eq!(msg.as_update(), Some((&1, &2)));
+        assert_eq!(msg.as_register(), None);
+        assert_eq!(
+            DeviceMessage::try_from(APMSG::Ping),
+            Ok(DeviceMessage::Ping)
+        );
+
+        let reset = APMSG::Reset(3);
+        assert!(!reset.is_device_message());
+        assert_eq!(reset.as_update(), None);
+        //转换失败的时候返回原来的值
+        assert_eq!(DeviceMessage::try_from(reset.clone()), Err(reset));
+
+        let c: ControllerMsg = DeviceMessage::Ping.into();
+        assert_eq!(c, ControllerMsg::Ping);
+        let c: ControllerMsg = APMSG::Reset(1).into();
+        assert_eq!(c, ControllerMsg::Reset(1));
+        assert!(c.is_apmsg());
+        assert!(!c.is_device_message());
+        assert_eq!(c.as_reset(), Some(&1));
+        assert_eq!(
+            APMSG::try_from(ControllerMsg::Shutdown),
+            Err(ControllerMsg::Shutdown)
+        );
+        assert_eq!(
+            ControllerMsg::Register("x".into())
+                .as_register()
+                .map(String::as_str),
+            Some("x")
+        );
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    pub trait Kind {
+        fn kind(&self) -> &'static str;
+    }
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum DeviceMessage {
+        Register(String),
+        Ping,
+    }
+
+    #[expand]
+    impl DeviceMessage {
+        pub fn code(&self) -> u8 {
+            match self {
+                Self::Register(_) => 1,
+                Self::Ping => 2,
+            }
+        }
+
+        pub fn name(&self) -> String {
+            match self {
+                DeviceMessage::Register(n) => n.clone(),
+                DeviceMessage::Ping => "ping".into(),
+            }
+        }
+
+        //已经有 _ 分支的不需要补
+        pub fn weight(&self) -> u32 {
+            match *self {
+                Self::Ping => 0,
+                _ => 10,
+            }
+        }
+
+        //不是variant名字的绑定也是兜底的分支
+        pub fn register_name(&self) -> Option<&str> {
+            match self {
+                Self::Register(n) => Some(n),
+                other => other.none(),
+            }
+        }
+
+        fn none(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    #[expand]
+    impl Kind for DeviceMessage {
+        fn kind(&self) -> &'static str {
+            match self {
+                Self::Register(_) => "register",
+                Self::Ping => "ping",
+            }
+        }
+    }
+
+    //新加的variant需要补上分支
+    #[overwrite]
+    impl APMSG {
+        #[mixin(arms)]
+        pub fn code(&self) -> u8 {
+            match self {
+                Self::Reset(_) => 3,
+            }
+        }
+
+        #[mixin(fallback)]
+        pub fn name(&self) -> String {
+            "ap".into()
+        }
+    }
+
+    #[overwrite]
+    impl Kind for APMSG {
+        fn kind(&self) -> &'static str {
+            match self {
+                Self::Reset(_) => "reset",
+                _ => "device",
+            }
+        }
+    }
+
+    #[insert(DeviceMessage)]
+    #[allow(clippy::upper_case_acronyms)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum APMSG {
+        Reset(i32),
+    }
+
+    #[test]
+    fn test_mixin() {
+        let r = APMSG::Register("r".into());
+        assert_eq!(r.code(), 1);
+        assert_eq!(APMSG::Ping.code(), 2);
+        assert_eq!(APMSG::Reset(1).code(), 3);
+        assert_eq!(r.name(), "r");
+        assert_eq!(APMSG::Ping.name(), "ping");
+        assert_eq!(APMSG::Reset(1).name(), "ap");
+        assert_eq!(APMSG::Ping.weight(), 0);
+        assert_eq!(APMSG::Reset(1).weight(), 10);
+        assert_eq!(r.register_name(), Some("r"));
+        assert_eq!(APMSG::Reset(1).register_name(), None);
+        assert_eq!(APMSG::Reset(1).kind(), "reset");
+        assert_eq!(r.kind(), "device");
+        assert_eq!(DeviceMessage::Ping.kind(), "ping");
+        assert_eq!(DeviceMessage::Ping.code(), 2);
+        assert_eq!(DeviceMessage::Ping.name(), "ping");
+        assert_eq!(DeviceMessage::Register("d".into()).weight(), 10);
+        assert_eq!(DeviceMessage::Ping.register_name(), None);
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum DeviceMessage {
+        Register(u32),
+        Ping,
+    }
+
+    #[expand]
+    impl DeviceMessage {
+        pub fn register_id(&self) -> Option<u32> {
+            match self {
+                DeviceMessage::Register(id) => Some(*id),
+                _ => None,
+            }
+        }
+
+        pub fn register(id: u32) -> Self {
+            Self::Register(id)
+        }
+
+        pub fn label(&self) -> String {
+            match self {
+                Self::Register(id) => format!("{:?}", Self::Register(*id)),
+                _ => "other".into(),
+            }
+        }
+    }
+
+    //Register和DeviceMessage的形状不一样，需要改名; Ping一样，直接合并
+    #[insert(DeviceMessage, rename(Register = DevRegister))]
+    #[allow(clippy::upper_case_acronyms)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum APMSG {
+        Register(String),
+        Ping,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let r = APMSG::register(7);
+        assert_eq!(r, APMSG::DevRegister(7));
+        assert_eq!(r.register_id(), Some(7));
+        assert_eq!(APMSG::Register("ap".into()).register_id(), None);
+        assert_eq!(r.label(), "DevRegister(7)");
+        assert_eq!(DeviceMessage::Register(1).label(), "Register(1)");
+        assert_eq!(DeviceMessage::register(2).register_id(), Some(2));
+
+        let from: APMSG = DeviceMessage::Register(3).into();
+        assert_eq!(from, APMSG::DevRegister(3));
+        assert_eq!(
+            DeviceMessage::try_from(from),
+            Ok(DeviceMessage::Register(3))
+        );
+        assert_eq!(
+            DeviceMessage::try_from(APMSG::Register("x".into())),
+            Err(APMSG::Register("x".into()))
+        );
+        //改过名的variant，as_按新的名字生成
+        assert_eq!(APMSG::DevRegister(5).as_dev_register(), Some(&5));
+        assert_eq!(APMSG::Ping.as_dev_register(), None);
+        assert!(APMSG::Ping.is_device_message());
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, insert, insert_fields, overwrite};
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct EventMeta {
+        pub timestamp: u64,
+        pub trace_id: String,
+        #[cfg(test)]
+        pub source: &'static str,
+    }
+
+    #[insert_fields(EventMeta)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Event {
+        Created { id: u32 },
+        Deleted { id: u32, soft: bool },
+        //已经有的字段不会重复添加
+        Renamed { to: String, timestamp: u64 },
+    }
+
+    //Event登记过了，可以继续insert
+    #[insert(Event)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Replayed {}
+
+    //没有insert，只有insert_fields的时候overwrite也会输出，同名的访问函数以overwrite的为准
+    #[overwrite]
+    impl Audit {
+        pub fn trace_id(&self) -> &String {
+            match self {
+                Audit::Login { user, .. } => user,
+            }
+        }
+
+        pub fn describe(&self) -> String {
+            format!("{} at {}", self.trace_id(), self.timestamp())
+        }
+    }
+
+    #[overwrite]
+    impl std::fmt::Display for Audit {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.describe())
+        }
+    }
+
+    #[insert_fields(EventMeta)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Audit {
+        Login { user: String },
+    }
+
+    #[test]
+    fn test_mixin() {
+        let e = Event::Created {
+            id: 1,
+            timestamp: 10,
+            trace_id: "t1".into(),
+            source: "api",
+        };
+        assert_eq!(*e.timestamp(), 10);
+        assert_eq!(e.trace_id(), "t1");
+        assert_eq!(*e.source(), "api");
+        assert_eq!(
+            e.get_event_meta(),
+            EventMeta {
+                timestamp: 10,
+                trace_id: "t1".into(),
+                source: "api",
+            }
+        );
+
+        let d = Event::Deleted {
+            id: 2,
+            soft: true,
+            timestamp: 20,
+            trace_id: "t2".into(),
+            source: "cli",
+        };
+        assert_eq!(*d.timestamp(), 20);
+        let r = Event::Renamed {
+            to: "x".into(),
+            timestamp: 30,
+            trace_id: "t3".into(),
+            source: "cli",
+        };
+        assert_eq!(r.get_event_meta().timestamp, 30);
+
+        let a = Audit::Login {
+            user: "u".into(),
+            timestamp: 40,
+            trace_id: "t4".into(),
+            source: "web",
+        };
+        assert_eq!(a.trace_id(), "u");
+        assert_eq!(a.to_string(), "u at 40");
+        assert_eq!(a.get_event_meta().trace_id, "t4");
+
+        let replayed: Replayed = e.into();
+        assert_eq!(replayed.trace_id(), "t1");
+        assert_eq!(replayed.get_event_meta().source, "api");
+    }
+}
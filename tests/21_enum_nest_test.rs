@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    pub trait Kind {
+        fn kind(&self) -> String;
+    }
+
+    pub trait Same {
+        fn same(&self, other: &Self) -> bool;
+    }
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum DeviceMessage {
+        Register(String),
+        Ping,
+    }
+
+    #[expand]
+    impl DeviceMessage {
+        pub fn ping() -> Self {
+            Self::Ping
+        }
+
+        pub fn code(&self) -> u8 {
+            match self {
+                Self::Register(_) => 1,
+                Self::Ping => 2,
+            }
+        }
+
+        pub fn rename(&mut self, name: &str) {
+            if let Self::Register(n) = self {
+                *n = name.into();
+            }
+        }
+
+        //参数里有Self的方法也转发不了，目标类型overwrite
+        pub fn same_code(&self, other: &Self) -> bool {
+            self.code() == other.code()
+        }
+    }
+
+    #[expand]
+    impl Kind for DeviceMessage {
+        fn kind(&self) -> String {
+            format!("device {}", self.code())
+        }
+    }
+
+    //参数里有Self，没办法转发，目标类型必须自己实现
+    #[expand]
+    impl Same for DeviceMessage {
+        fn same(&self, other: &Self) -> bool {
+            self.code() == other.code()
+        }
+    }
+
+    #[overwrite]
+    impl Same for APMSG {
+        fn same(&self, other: &Self) -> bool {
+            self.code() == other.code()
+        }
+    }
+
+    #[overwrite]
+    impl Same for Wrapped {
+        fn same(&self, _other: &Self) -> bool {
+            true
+        }
+    }
+
+    //新的variant需要补上分支
+    #[overwrite]
+    impl APMSG {
+        #[mixin(arms)]
+        pub fn code(&self) -> u8 {
+            match self {
+                Self::Reset(_) => 3,
+            }
+        }
+
+        #[mixin(fallback)]
+        pub fn rename(&mut self, _name: &str) {}
+
+        pub fn same_code(&self, other: &Self) -> bool {
+            self.code() == other.code()
+        }
+    }
+
+    #[overwrite]
+    impl Wrapped {
+        pub fn same_code(&self, _other: &Self) -> bool {
+            true
+        }
+    }
+
+    #[overwrite]
+    impl Kind for APMSG {
+        fn kind(&self) -> String {
+            match self {
+                Self::Device(d) => d.kind(),
+                Self::Reset(n) => format!("reset {}", n),
+            }
+        }
+    }
+
+    #[insert(DeviceMessage, nest = Device)]
+    #[allow(clippy::upper_case_acronyms)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum APMSG {
+        Reset(i32),
+    }
+
+    //没有新的variant时不需要overwrite
+    #[insert(DeviceMessage, nest)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Wrapped {}
+
+    #[test]
+    fn test_mixin() {
+        let ping = APMSG::ping();
+        assert_eq!(ping, APMSG::Device(DeviceMessage::Ping));
+        assert_eq!(ping.code(), 2);
+        assert_eq!(APMSG::Reset(1).code(), 3);
+        assert_eq!(ping.kind(), "device 2");
+        assert_eq!(APMSG::Reset(1).kind(), "reset 1");
+
+        let mut msg: APMSG = DeviceMessage::Register("a".into()).into();
+        msg.rename("b");
+        assert_eq!(
+            msg.as_device_message(),
+            Some(&DeviceMessage::Register("b".into()))
+        );
+        assert!(msg.is_device_message());
+        assert!(!APMSG::Reset(1).is_device_message());
+        assert_eq!(APMSG::Reset(1).as_device_message(), None);
+        assert_eq!(
+            DeviceMessage::try_from(msg),
+            Ok(DeviceMessage::Register("b".into()))
+        );
+        assert_eq!(
+            DeviceMessage::try_from(APMSG::Reset(2)),
+            Err(APMSG::Reset(2))
+        );
+
+        let w = Wrapped::from(DeviceMessage::Ping);
+        assert_eq!(w, Wrapped::DeviceMessage(DeviceMessage::Ping));
+        assert_eq!(w.code(), 2);
+        assert_eq!(w.kind(), "device 2");
+        assert_eq!(Wrapped::ping(), w);
+        assert!(ping.same(&APMSG::ping()));
+        assert!(!ping.same(&APMSG::Reset(1)));
+        assert!(w.same(&w));
+        assert!(ping.same_code(&APMSG::ping()));
+        assert!(!ping.same_code(&APMSG::Reset(1)));
+        assert!(w.same_code(&w));
+        assert!(DeviceMessage::Ping.same_code(&DeviceMessage::Ping));
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    pub trait Greet {
+        fn greet(&self) -> String;
+    }
+
+    pub trait Older {
+        fn older(&self, other: &Self) -> bool;
+    }
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Person {
+        pub name: String,
+        age: i32,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn new(name: &str) -> Person {
+            Person {
+                name: name.into(),
+                age: 0,
+            }
+        }
+
+        pub fn age(&self) -> i32 {
+            self.age
+        }
+
+        //字段要一起修改，所以不希望展开
+        pub fn birthday(&mut self) {
+            self.age += 1;
+        }
+
+        pub fn describe(&self, prefix: &str) -> String {
+            format!("{} {} {}", prefix, self.name, self.age)
+        }
+
+        //参数里有Self的方法也转发不了，目标类型overwrite
+        pub fn same_age(&self, other: &Self) -> bool {
+            self.age == other.age
+        }
+    }
+
+    #[expand]
+    impl Greet for Person {
+        fn greet(&self) -> String {
+            format!("hi {}", self.name)
+        }
+    }
+
+    #[expand]
+    impl Default for Person {
+        fn default() -> Self {
+            Person::new("nobody")
+        }
+    }
+
+    //参数里有Self，没办法转发给person字段，目标类型必须自己实现
+    #[expand]
+    impl Older for Person {
+        fn older(&self, other: &Self) -> bool {
+            self.age > other.age
+        }
+    }
+
+    #[overwrite]
+    impl Older for Employee {
+        fn older(&self, other: &Self) -> bool {
+            self.person.older(&other.person)
+        }
+    }
+
+    #[overwrite]
+    impl Older for Student {
+        fn older(&self, other: &Self) -> bool {
+            self.age() > other.age()
+        }
+    }
+
+    #[overwrite]
+    impl Employee {
+        pub fn describe(&self, prefix: &str) -> String {
+            format!("{} {} at {}", prefix, self.person.name, self.company)
+        }
+
+        pub fn same_age(&self, other: &Self) -> bool {
+            self.person.same_age(&other.person)
+        }
+    }
+
+    #[overwrite]
+    impl Student {
+        pub fn same_age(&self, other: &Self) -> bool {
+            self.age() == other.age()
+        }
+    }
+
+    #[insert(Person, embed = person, deref, as_ref)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Employee {
+        pub company: String,
+    }
+
+    #[insert(Person, embed, ctor = default)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Student {
+        pub school: String,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let mut e = Employee::new("xjplke", "acme".into());
+        assert_eq!(e.person, Person::new("xjplke"));
+        assert_eq!(e.company, "acme");
+        e.birthday();
+        assert_eq!(e.age(), 1);
+        assert_eq!(e.greet(), "hi xjplke");
+        assert_eq!(e.describe("employee"), "employee xjplke at acme");
+        //Deref/AsRef
+        assert_eq!(e.name, "xjplke");
+        let p: &Person = e.as_ref();
+        assert_eq!(p.describe("person"), "person xjplke 1");
+        e.as_mut().name = "other".into();
+        assert_eq!(e.get_person().name, "other");
+
+        let d = Employee::default();
+        assert_eq!(d.person, Person::new("nobody"));
+        assert_eq!(d.company, "");
+
+        let mut s = Student::new("tom");
+        s.birthday();
+        assert_eq!(s.age(), 1);
+        assert_eq!(s.school, "");
+        assert_eq!(s.describe("student"), "student tom 1");
+        assert!(s.older(&Student::default()));
+        assert!(!e.older(&e.clone()));
+        assert!(e.same_age(&e.clone()));
+        assert!(!s.same_age(&Student::default()));
+    }
+}
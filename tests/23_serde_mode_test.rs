@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, insert};
+    use serde::{Deserialize, Serialize};
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq, Default, Deserialize, Serialize)]
+    pub struct Meta {
+        #[serde(rename = "Id")]
+        pub id: u32,
+        pub tag: String,
+    }
+
+    //默认展开字段
+    #[insert(Meta)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    pub struct Flat {
+        pub value: i32,
+    }
+
+    #[insert(Meta, serde = flatten)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    pub struct Flattened {
+        pub value: i32,
+    }
+
+    #[insert(Meta, serde = nested)]
+    #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+    pub struct Nested {
+        pub value: i32,
+    }
+
+    //没有derive serde，字段上的 #[serde] 需要去掉
+    #[insert(Meta)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Plain {
+        pub value: i32,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let meta = Meta {
+            id: 1,
+            tag: "t".into(),
+        };
+
+        let flat = Flat {
+            id: 1,
+            tag: "t".into(),
+            value: 2,
+        };
+        let json = r#"{"value":2,"Id":1,"tag":"t"}"#;
+        assert_eq!(serde_json::to_string(&flat).unwrap(), json);
+
+        let flattened = Flattened {
+            meta: meta.clone(),
+            value: 2,
+        };
+        assert_eq!(
+            serde_json::to_string(&flattened).unwrap(),
+            r#"{"value":2,"Id":1,"tag":"t"}"#
+        );
+        assert_eq!(serde_json::from_str::<Flattened>(json).unwrap(), flattened);
+
+        let nested = Nested {
+            meta: meta.clone(),
+            value: 2,
+        };
+        let nested_json = r#"{"value":2,"meta":{"Id":1,"tag":"t"}}"#;
+        assert_eq!(serde_json::to_string(&nested).unwrap(), nested_json);
+        assert_eq!(serde_json::from_str::<Nested>(nested_json).unwrap(), nested);
+
+        //三种方式的访问函数都一样
+        assert_eq!(flat.get_meta(), meta);
+        assert_eq!(flattened.get_meta(), meta);
+        assert_eq!(nested.get_meta(), meta);
+        let mut plain = Plain {
+            id: 0,
+            tag: "".into(),
+            value: 3,
+        };
+        plain.set_meta(&meta);
+        assert_eq!(plain.get_meta(), meta);
+        let mut n = nested.clone();
+        n.set_meta(&Meta::default());
+        assert_eq!(n.get_meta(), Meta::default());
+    }
+}
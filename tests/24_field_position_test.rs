@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, insert};
+
+    #[declare]
+    #[repr(C)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Header {
+        pub version: u8,
+        pub len: u32,
+    }
+
+    #[declare]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Trailer {
+        pub crc: u32,
+    }
+
+    #[insert(Header, position = first, check_layout)]
+    #[repr(C)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Packet {
+        pub payload: [u8; 4],
+    }
+
+    #[insert(Trailer, after = id, Header, position = first)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Frame {
+        pub id: u16,
+        pub body: String,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let p = Packet {
+            version: 1,
+            len: 4,
+            payload: [1, 2, 3, 4],
+        };
+        assert_eq!(
+            format!("{:?}", p),
+            "Packet { version: 1, len: 4, payload: [1, 2, 3, 4] }"
+        );
+        assert_eq!(
+            core::mem::offset_of!(Packet, len),
+            core::mem::offset_of!(Header, len)
+        );
+
+        let f = Frame {
+            version: 2,
+            len: 0,
+            id: 7,
+            crc: 9,
+            body: "b".into(),
+        };
+        assert_eq!(
+            format!("{:?}", f),
+            r#"Frame { version: 2, len: 0, id: 7, crc: 9, body: "b" }"#
+        );
+        assert_eq!(f.get_trailer(), Trailer { crc: 9 });
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+
+    #[declare]
+    #[repr(C)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Person {
+        pub id: u64,
+        pub age: u32,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn is_adult(&self) -> bool {
+            self.age >= 18
+        }
+    }
+
+    fn read_age(p: &Person) -> u32 {
+        p.age
+    }
+
+    #[insert(Person, prefix)]
+    #[repr(C)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Employee {
+        pub salary: u64,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let mut e = Employee {
+            id: 1,
+            age: 30,
+            salary: 100,
+        };
+        let p: &Person = e.as_person();
+        assert_eq!(p, &Person { id: 1, age: 30 });
+        assert_eq!(read_age(e.as_person()), 30);
+        assert!(e.as_person().is_adult());
+
+        e.as_person_mut().age = 31;
+        assert_eq!(e.age, 31);
+        *e.as_person_mut() = Person { id: 2, age: 5 };
+        assert_eq!(e.id, 2);
+        assert_eq!(e.salary, 100);
+        assert!(!e.is_adult());
+    }
+}
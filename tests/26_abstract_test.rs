@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+
+    pub trait Touch {
+        fn touch(&mut self, now: u64);
+    }
+
+    //Timestamps本身不会输出，只能insert
+    #[declare(abstract)]
+    pub struct Timestamps {
+        pub created_at: u64,
+        pub updated_at: u64,
+    }
+
+    #[expand]
+    impl Timestamps {
+        pub fn age(&self, now: u64) -> u64 {
+            now - self.created_at
+        }
+
+        //构造函数没办法继承
+        pub fn new() -> Timestamps {
+            Timestamps {
+                created_at: 0,
+                updated_at: 0,
+            }
+        }
+    }
+
+    #[expand]
+    impl Touch for Timestamps {
+        fn touch(&mut self, now: u64) {
+            self.updated_at = now;
+        }
+    }
+
+    #[declare(abstract)]
+    pub enum Lifecycle {
+        Created,
+        Deleted,
+    }
+
+    #[insert(Timestamps)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Order {
+        pub id: u32,
+    }
+
+    #[insert(Lifecycle)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum OrderEvent {
+        Paid,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let mut o = Order {
+            id: 1,
+            created_at: 10,
+            updated_at: 10,
+        };
+        assert_eq!(o.age(15), 5);
+        o.touch(20);
+        assert_eq!(o.updated_at, 20);
+        assert_eq!(o.get_order().id, 1);
+
+        let events = [OrderEvent::Created, OrderEvent::Paid, OrderEvent::Deleted];
+        assert_eq!(events.len(), 3);
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    #[declare(abstract)]
+    pub struct Entity {
+        pub name: String,
+    }
+
+    #[expand]
+    impl Entity {
+        #[required]
+        fn id(&self) -> u64;
+
+        pub fn label(&self) -> String {
+            format!("{}#{}", self.name, self.id())
+        }
+    }
+
+    #[overwrite]
+    impl User {
+        fn id(&self) -> u64 {
+            self.uid
+        }
+    }
+
+    #[insert(Entity)]
+    pub struct User {
+        pub uid: u64,
+    }
+
+    //User已经实现了id，继续insert不需要再实现
+    #[insert(User)]
+    pub struct Admin {
+        pub level: u8,
+    }
+
+    //不是abstract的mixin也可以有#[required]，但它自己的实现要写在#[expand]外面，不会被继承
+    #[declare]
+    pub struct Node {
+        pub name: String,
+    }
+
+    #[expand]
+    impl Node {
+        #[required]
+        fn key(&self) -> String;
+
+        pub fn path(&self) -> String {
+            format!("/{}", self.key())
+        }
+    }
+
+    impl Node {
+        fn key(&self) -> String {
+            self.name.clone()
+        }
+    }
+
+    #[overwrite]
+    impl File {
+        fn key(&self) -> String {
+            format!("{}.{}", self.name, self.ext)
+        }
+    }
+
+    #[insert(Node)]
+    pub struct File {
+        pub ext: String,
+    }
+
+    #[test]
+    fn test_mixin() {
+        let u = User {
+            name: "u".into(),
+            uid: 7,
+        };
+        assert_eq!(u.label(), "u#7");
+        let a = Admin {
+            name: "a".into(),
+            uid: 1,
+            level: 9,
+        };
+        assert_eq!(a.label(), "a#1");
+        assert_eq!(a.level, 9);
+
+        let n = Node { name: "n".into() };
+        assert_eq!(n.path(), "/n");
+        let f = File {
+            name: "f".into(),
+            ext: "rs".into(),
+        };
+        assert_eq!(f.path(), "/f.rs");
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, insert};
+
+    #[declare(final)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Secret {
+        pub key: String,
+    }
+
+    #[declare(allow_insert_in = "crate::tests::devices")]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct DeviceBase {
+        pub name: String,
+    }
+
+    pub mod devices {
+        use crate::tests::DeviceBase;
+        use mixinx::insert;
+
+        #[insert(DeviceBase)]
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct Switch {
+            pub ports: u8,
+        }
+
+        //子模块里也可以
+        pub mod wireless {
+            use crate::tests::DeviceBase;
+            use mixinx::insert;
+
+            #[insert(DeviceBase)]
+            #[derive(Clone, Debug, PartialEq)]
+            pub struct AP {
+                pub ssid: String,
+            }
+        }
+    }
+
+    #[declare(allow_insert_in = "crate")]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Anywhere {
+        pub id: u32,
+    }
+
+    #[insert(Anywhere)]
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Here {}
+
+    #[test]
+    fn test_mixin() {
+        let s = devices::Switch {
+            name: "sw".into(),
+            ports: 8,
+        };
+        assert_eq!(s.get_device_base().name, "sw");
+        let ap = devices::wireless::AP {
+            name: "ap".into(),
+            ssid: "x".into(),
+        };
+        assert_eq!(ap.name, "ap");
+        assert_eq!(Here { id: 1 }.get_anywhere(), Anywhere { id: 1 });
+        assert_eq!(Secret { key: "k".into() }.get_secret().key, "k");
+    }
+}
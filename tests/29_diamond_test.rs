@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert, overwrite};
+
+    #[declare]
+    pub struct Person {
+        pub name: String,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn describe(&self) -> String {
+            format!("person {}", self.name)
+        }
+
+        pub fn greet(&self) -> String {
+            format!("hi {}", self.name)
+        }
+    }
+
+    #[expand]
+    impl std::fmt::Display for Person {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "person")
+        }
+    }
+
+    #[overwrite]
+    impl Student {
+        pub fn describe(&self) -> String {
+            format!("student {} at {}", self.name, self.school)
+        }
+    }
+
+    #[insert(Person)]
+    pub struct Student {
+        pub school: String,
+    }
+
+    #[overwrite]
+    impl std::fmt::Display for Teacher {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "teacher of {}", self.subject)
+        }
+    }
+
+    #[insert(Person)]
+    pub struct Teacher {
+        pub subject: String,
+    }
+
+    //Student的describe比经由Teacher过来的Person::describe更具体，不管insert的顺序
+    #[insert(Student, Teacher)]
+    pub struct Employee {
+        pub salary: u32,
+    }
+
+    #[insert(Teacher, Student)]
+    pub struct Assistant {
+        pub hours: u32,
+    }
+
+    //两条路径都overwrite了Person::describe，目标类型必须自己overwrite
+    #[overwrite]
+    impl Tutor {
+        pub fn describe(&self) -> String {
+            format!("tutor {}", self.name)
+        }
+    }
+
+    #[overwrite]
+    impl Mentor {
+        pub fn describe(&self) -> String {
+            format!("mentor {}", self.name)
+        }
+    }
+
+    #[insert(Person)]
+    pub struct Mentor {}
+
+    #[insert(Student, Mentor)]
+    pub struct Tutor {}
+
+    //构造函数也按来源挑: Left::new和Right::new都是从Top::new来的，用先insert的Left::new
+    #[declare]
+    pub struct Top {
+        pub id: u32,
+    }
+
+    #[expand]
+    impl Top {
+        pub fn new(id: u32) -> Self {
+            Self { id }
+        }
+    }
+
+    #[insert(Top)]
+    pub struct Left {
+        pub l: u32,
+    }
+
+    #[insert(Top)]
+    pub struct Right {
+        pub r: u32,
+    }
+
+    #[insert(Left, Right)]
+    pub struct Bottom {
+        pub b: u32,
+    }
+
+    //Special自己overwrite了new，比经由Left过来的Top::new更具体
+    #[overwrite]
+    impl Special {
+        pub fn new(id: u32) -> Self {
+            Self { id: id * 10, s: 9 }
+        }
+    }
+
+    #[insert(Top)]
+    pub struct Special {
+        pub s: u32,
+    }
+
+    #[insert(Left, Special)]
+    pub struct Corner {}
+
+    #[test]
+    fn test_ctor() {
+        let b = Bottom::new(1, 2, 3, 4);
+        assert_eq!((b.id, b.l, b.b, b.r), (1, 2, 3, 4));
+        let c = Corner::new(1, 2);
+        assert_eq!((c.id, c.s, c.l), (10, 9, 2));
+    }
+
+    #[test]
+    fn test_mixin() {
+        let p = Person { name: "p".into() };
+        assert_eq!(p.describe(), "person p");
+        assert_eq!(p.greet(), "hi p");
+        assert_eq!(p.to_string(), "person");
+        let s = Student {
+            name: "s".into(),
+            school: "s".into(),
+        };
+        assert_eq!(s.describe(), "student s at s");
+        let t = Teacher {
+            name: "t".into(),
+            subject: "math".into(),
+        };
+        assert_eq!(t.describe(), "person t");
+        assert_eq!(t.to_string(), "teacher of math");
+        let m = Mentor { name: "m".into() };
+        assert_eq!(m.describe(), "mentor m");
+        let e = Employee {
+            name: "e".into(),
+            school: "s".into(),
+            subject: "math".into(),
+            salary: 1,
+        };
+        assert_eq!(e.describe(), "student e at s");
+        assert_eq!(e.greet(), "hi e");
+        assert_eq!(e.to_string(), "teacher of math");
+        assert_eq!(e.salary, 1);
+        let a = Assistant {
+            name: "a".into(),
+            subject: "math".into(),
+            school: "s".into(),
+            hours: 2,
+        };
+        assert_eq!(a.describe(), "student a at s");
+        assert_eq!(a.to_string(), "teacher of math");
+        assert_eq!(a.hours, 2);
+        let t = Tutor {
+            name: "t".into(),
+            school: "s".into(),
+        };
+        assert_eq!(t.describe(), "tutor t");
+        assert_eq!(t.greet(), "hi t");
+    }
+}
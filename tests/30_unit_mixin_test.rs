@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+
+    //没有字段的mixin只有方法，没有get/set，可以insert到tuple struct和unit struct上
+    #[declare]
+    pub struct Named;
+
+    #[expand]
+    impl Named {
+        pub fn kind(&self) -> &'static str {
+            std::any::type_name::<Self>()
+                .rsplit("::")
+                .next()
+                .unwrap_or_default()
+        }
+    }
+
+    #[insert(Named)]
+    pub struct Meters(pub f64);
+
+    #[insert(Named)]
+    pub struct Origin;
+
+    #[test]
+    fn test_mixin() {
+        assert_eq!(Named.kind(), "Named");
+        let m = Meters(1.5);
+        assert_eq!(m.kind(), "Meters");
+        assert_eq!(m.0, 1.5);
+        assert_eq!(Origin.kind(), "Origin");
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, insert};
+    use std::path::{Path, PathBuf};
+
+    #[declare]
+    pub struct Person {
+        pub name: String,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn hello(&self) -> String {
+            format!("hello {}", self.name)
+        }
+    }
+
+    #[expand]
+    impl std::fmt::Display for Person {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.name)
+        }
+    }
+
+    #[insert(Person)]
+    pub struct Student {
+        pub school: String,
+    }
+
+    //dump: 展开的结果写到 <target>/mixinx/<crate>/Employee.rs
+    #[insert(Student, dump)]
+    pub struct Employee {
+        pub company: String,
+    }
+
+    //和dump_item一样: MIXINX_DUMP，否则CARGO_TARGET_DIR或者workspace的target下的mixinx
+    fn dump_dir() -> PathBuf {
+        let dir = match (option_env!("MIXINX_DUMP"), option_env!("CARGO_TARGET_DIR")) {
+            (Some(dir), _) => PathBuf::from(dir),
+            (None, Some(target)) => Path::new(target).join("mixinx"),
+            (None, None) => Path::new(env!("CARGO_MANIFEST_DIR")).join("target/mixinx"),
+        };
+        dir.join(env!("CARGO_CRATE_NAME"))
+    }
+
+    #[test]
+    fn test_mixin() {
+        let p = Person { name: "p".into() };
+        assert_eq!(p.hello(), "hello p");
+        let s = Student {
+            school: "s".into(),
+            name: "s".into(),
+        };
+        assert_eq!(s.to_string(), "s");
+        assert_eq!(s.school, "s");
+        let e = Employee {
+            company: "c".into(),
+            school: "s".into(),
+            name: "e".into(),
+        };
+        assert_eq!(e.hello(), "hello e");
+
+        let dump = std::fs::read_to_string(dump_dir().join("Employee.rs")).unwrap();
+        assert!(dump
+            .starts_with("// `Employee`, expanded by mixinx\n// ancestors: Student -> Person\n"));
+        let origin = |item: &str| {
+            dump.lines()
+                .find(|line| line.split_whitespace().nth(1) == Some(item))
+                .and_then(|line| line.rsplit(' ').next())
+                .unwrap_or_default()
+                .to_string()
+        };
+        assert_eq!(origin("company"), "Employee");
+        assert_eq!(origin("school"), "Student");
+        assert_eq!(origin("name"), "Person");
+        assert_eq!(origin("hello"), "Person");
+        assert_eq!(origin("get_student"), "Student");
+        assert_eq!(origin("get_employee"), "Employee");
+        assert_eq!(origin("std::fmt::Display"), "Person");
+        //格式化过的源码
+        assert!(dump.contains("pub struct Employee {\n    pub company: String,"));
+        assert!(dump.contains("impl std::fmt::Display for Employee {"));
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, expand, graph, insert, overwrite};
+
+    #[declare]
+    pub struct Person {
+        pub name: String,
+    }
+
+    #[expand]
+    impl Person {
+        pub fn hello(&self) -> String {
+            format!("hello {}", self.name)
+        }
+    }
+
+    #[overwrite]
+    impl Student {
+        pub fn hello(&self) -> String {
+            format!("hello {} from {}", self.name, self.school)
+        }
+    }
+
+    #[insert(Person)]
+    pub struct Student {
+        pub school: String,
+    }
+
+    //graph!()只能看到它之前展开的mixin
+    const GRAPH: &str = graph!();
+    const DOT: &str = graph!(dot);
+
+    #[test]
+    fn test_mixin() {
+        let s = Student {
+            name: "s".into(),
+            school: "x".into(),
+        };
+        assert_eq!(s.hello(), "hello s from x");
+        assert_eq!(Person { name: "p".into() }.hello(), "hello p");
+
+        let graph: serde_json::Value = serde_json::from_str(GRAPH).unwrap();
+        let mixins = graph["mixins"].as_array().unwrap();
+        let names: Vec<&str> = mixins.iter().map(|m| m["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["Person", "Student"]);
+
+        let student = &mixins[1];
+        assert_eq!(student["kind"], "struct");
+        assert_eq!(student["abstract"], false);
+        assert_eq!(student["parents"], serde_json::json!(["Person"]));
+        assert_eq!(student["ancestors"], serde_json::json!(["Person"]));
+        assert_eq!(
+            student["fields"],
+            serde_json::json!([
+                { "name": "school", "type": "String", "from": "Student" },
+                { "name": "name", "type": "String", "from": "Person" },
+            ])
+        );
+        let method_from = |name: &str| {
+            student["methods"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|m| m["name"] == name)
+                .map(|m| m["from"].as_str().unwrap().to_string())
+        };
+        assert_eq!(method_from("hello").as_deref(), Some("Student"));
+        assert_eq!(method_from("get_person").as_deref(), Some("Person"));
+        assert_eq!(method_from("get_student").as_deref(), Some("Student"));
+        assert_eq!(student["overwrites"], serde_json::json!(["hello"]));
+
+        assert!(DOT.starts_with("digraph mixinx {"));
+        assert!(DOT.contains("\"Student\" -> \"Person\";"));
+        assert!(DOT.contains("name: String (Person)\\l"));
+    }
+}
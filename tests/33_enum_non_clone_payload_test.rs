@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use mixinx::{declare, insert};
+
+    //payload没有实现Clone，as_返回的是引用
+    #[derive(Debug, PartialEq)]
+    pub struct NoClone(u8);
+
+    #[declare]
+    #[derive(Debug, PartialEq)]
+    pub enum Msg {
+        Data(NoClone),
+        Ping,
+    }
+
+    #[insert(Msg)]
+    #[derive(Debug, PartialEq)]
+    pub enum All {
+        Other,
+    }
+
+    //泛型的enum mixin，目标类型声明同名的泛型参数
+    #[declare]
+    #[derive(Debug, PartialEq)]
+    pub enum Reply<T> {
+        Value(T),
+        Empty,
+    }
+
+    #[insert(Reply)]
+    #[derive(Debug, PartialEq)]
+    pub enum Response<T> {
+        Failed { code: u16, reason: String },
+    }
+
+    #[test]
+    fn test_mixin() {
+        let all: All = Msg::Data(NoClone(1)).into();
+        assert!(all.is_msg());
+        assert_eq!(all.as_data(), Some(&NoClone(1)));
+        assert_eq!(All::Other.as_data(), None);
+        assert!(!All::Other.is_msg());
+        assert_eq!(Msg::try_from(All::Ping), Ok(Msg::Ping));
+        assert_eq!(Msg::try_from(All::Other), Err(All::Other));
+
+        let r: Response<u8> = Reply::Value(3).into();
+        assert_eq!(r.as_value(), Some(&3));
+        assert!(r.is_reply());
+        let failed = Response::<u8>::Failed {
+            code: 500,
+            reason: "down".into(),
+        };
+        assert!(!failed.is_reply());
+        assert_eq!(Reply::try_from(Response::<u8>::Empty), Ok(Reply::Empty));
+    }
+}
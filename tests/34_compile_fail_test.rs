@@ -0,0 +1,9 @@
+#[cfg(test)]
+mod tests {
+    //编译不过的用法，检查报错的位置和内容
+    #[test]
+    fn test_compile_fail() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/ui/*.rs");
+    }
+}
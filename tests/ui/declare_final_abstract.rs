@@ -0,0 +1,9 @@
+use mixinx::declare;
+
+//final的mixin不能insert，abstract没有意义
+#[declare(final, abstract)]
+pub struct Person {
+    pub name: String,
+}
+
+fn main() {}
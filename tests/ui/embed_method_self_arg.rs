@@ -0,0 +1,21 @@
+use mixinx::{declare, expand, insert};
+
+#[declare]
+pub struct Person {
+    pub age: i32,
+}
+
+#[expand]
+impl Person {
+    pub fn older(&self, other: &Self) -> bool {
+        self.age > other.age
+    }
+}
+
+//other: &Self没办法转发给person字段
+#[insert(Person, embed = person)]
+pub struct Employee {
+    pub company: String,
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use mixinx::{declare, expand, insert};
+
+pub trait Older {
+    fn older(&self, other: &Self) -> bool;
+}
+
+#[declare]
+pub struct Person {
+    pub age: i32,
+}
+
+#[expand]
+impl Older for Person {
+    fn older(&self, other: &Self) -> bool {
+        self.age > other.age
+    }
+}
+
+//other: &Self没办法转发给person字段
+#[insert(Person, embed = person)]
+pub struct Employee {
+    pub company: String,
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use mixinx::{declare, expand, insert, overwrite};
+
+#[declare]
+pub enum DeviceMessage {
+    Register(String),
+    Ping,
+}
+
+#[expand]
+impl DeviceMessage {
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Register(_) => 1,
+            Self::Ping => 2,
+        }
+    }
+}
+
+//忘了 #[mixin(arms)]，这就是一个完整的overwrite，match不完整
+#[overwrite]
+impl ApMsg {
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Reset(_) => 3,
+        }
+    }
+}
+
+#[insert(DeviceMessage)]
+pub enum ApMsg {
+    Reset(i32),
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+use mixinx::{declare, expand, insert};
+
+#[declare]
+pub enum DeviceMessage {
+    Register(String),
+    Ping,
+}
+
+#[expand]
+impl DeviceMessage {
+    //不是最后一个表达式的match self没办法补分支
+    pub fn code(&self) -> u8 {
+        let code = match self {
+            Self::Register(_) => 1,
+            Self::Ping => 2,
+        };
+        code + 1
+    }
+}
+
+#[insert(DeviceMessage)]
+pub enum ApMsg {
+    Reset(i32),
+}
+
+fn main() {}
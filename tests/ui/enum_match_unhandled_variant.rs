@@ -0,0 +1,25 @@
+use mixinx::{declare, expand, insert};
+
+#[declare]
+pub enum DeviceMessage {
+    Register(String),
+    Ping,
+}
+
+#[expand]
+impl DeviceMessage {
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Register(_) => 1,
+            Self::Ping => 2,
+        }
+    }
+}
+
+//Reset没有分支，也没有overwrite补上
+#[insert(DeviceMessage)]
+pub enum ApMsg {
+    Reset(i32),
+}
+
+fn main() {}
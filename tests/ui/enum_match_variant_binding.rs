@@ -0,0 +1,27 @@
+use mixinx::{declare, expand, insert};
+
+#[declare]
+pub enum DeviceMessage {
+    Register(String),
+    Ping,
+}
+
+#[expand]
+impl DeviceMessage {
+    //Ping是variant，不是兜底的绑定
+    pub fn is_ping(&self) -> bool {
+        #[allow(unused_imports)]
+        use DeviceMessage::*;
+        match *self {
+            Ping => true,
+            Register(_) => false,
+        }
+    }
+}
+
+#[insert(DeviceMessage)]
+pub enum ApMsg {
+    Reset(i32),
+}
+
+fn main() {}
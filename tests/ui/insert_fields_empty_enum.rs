@@ -0,0 +1,12 @@
+use mixinx::{declare, insert_fields};
+
+#[declare]
+pub struct EventMeta {
+    pub timestamp: u64,
+}
+
+//没有variant可以加字段
+#[insert_fields(EventMeta)]
+pub enum Event {}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use mixinx::{declare, expand, insert};
+
+#[declare]
+pub enum DeviceMessage {
+    Ping,
+}
+
+#[expand]
+impl DeviceMessage {
+    pub fn same(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+//other: &Self没办法转发给包起来的DeviceMessage
+#[insert(DeviceMessage, nest = Device)]
+pub enum ApMsg {
+    Reset(i32),
+}
+
+fn main() {}
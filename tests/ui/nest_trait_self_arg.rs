@@ -0,0 +1,25 @@
+use mixinx::{declare, expand, insert};
+
+pub trait Same {
+    fn same(&self, other: &Self) -> bool;
+}
+
+#[declare]
+pub enum DeviceMessage {
+    Ping,
+}
+
+#[expand]
+impl Same for DeviceMessage {
+    fn same(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+//other: &Self没办法转发给包起来的DeviceMessage
+#[insert(DeviceMessage, nest = Device)]
+pub enum ApMsg {
+    Reset(i32),
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use mixinx::{declare, expand, insert};
+
+#[declare]
+pub struct Node {
+    pub name: String,
+}
+
+#[expand]
+impl Node {
+    #[required]
+    fn key(&self) -> String;
+
+    pub fn path(&self) -> String {
+        format!("/{}", self.key())
+    }
+}
+
+//Node自己的实现不会继承，File必须自己overwrite
+impl Node {
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[insert(Node)]
+pub struct File {
+    pub ext: String,
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use mixinx::{declare, insert};
+
+#[declare]
+pub struct Person {
+    pub name: String,
+}
+
+//registry按类型名登记，别的模块里的同名类型不能再声明
+mod first {
+    use super::*;
+
+    #[insert(Person)]
+    pub struct Worker {
+        pub shift: u8,
+    }
+}
+
+mod second {
+    use super::*;
+
+    #[insert(Person)]
+    pub struct Worker {
+        pub desk: u8,
+    }
+}
+
+fn main() {}